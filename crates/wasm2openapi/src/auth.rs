@@ -0,0 +1,351 @@
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{AUTHORIZATION, WWW_AUTHENTICATE};
+use actix_web::http::StatusCode;
+use actix_web::HttpRequest;
+use anyhow::{anyhow, bail, Context};
+use jsonwebtoken::jwk::{AlgorithmParameters, Jwk, JwkSet};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+
+use crate::error::ApiError;
+
+/// Validates the bearer tokens of incoming requests against a JSON Web Key Set.
+pub struct JwtValidator {
+    jwks: JwkSet,
+    audience: Option<String>,
+    issuer: Option<String>,
+}
+
+impl JwtValidator {
+    pub async fn fetch(
+        jwks_url: &str,
+        audience: Option<String>,
+        issuer: Option<String>,
+    ) -> anyhow::Result<Self> {
+        let jwks = reqwest::get(jwks_url)
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("Failed to fetch the JWKS from {}", jwks_url))?
+            .json::<JwkSet>()
+            .await
+            .with_context(|| format!("Failed to parse the JWKS from {}", jwks_url))?;
+
+        Ok(Self {
+            jwks,
+            audience,
+            issuer,
+        })
+    }
+
+    pub fn validate(&self, token: &str) -> anyhow::Result<()> {
+        let header = decode_header(token)?;
+        let jwk = match &header.kid {
+            Some(kid) => self.jwks.find(kid),
+            // Without a key ID, only an unambiguous key set can be used
+            None if self.jwks.keys.len() == 1 => self.jwks.keys.first(),
+            None => None,
+        }
+        .ok_or_else(|| anyhow!("no matching key"))?;
+        let algorithm = algorithm(jwk, header.alg)?;
+
+        let mut validation = Validation::new(algorithm);
+        match &self.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+
+        decode::<serde_json::Value>(token, &DecodingKey::from_jwk(jwk)?, &validation)?;
+
+        Ok(())
+    }
+
+    /// Check the `Authorization` header of a request.
+    pub fn authorize(&self, request: &HttpRequest) -> Result<(), ApiError> {
+        let token = request
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| anyhow!("missing bearer token"));
+
+        token
+            .and_then(|token| self.validate(token.trim()).context("invalid token"))
+            .map_err(|e| {
+                ApiError::new(StatusCode::UNAUTHORIZED, format!("{:#}", e))
+                    .header((WWW_AUTHENTICATE, "Bearer error=\"invalid_token\""))
+            })
+    }
+}
+
+/// The algorithm a token must be signed with by a key: the one the key declares, rather than the
+/// one of the token header, which anyone can set, or else any algorithm of the key's type.
+fn algorithm(jwk: &Jwk, token: Algorithm) -> anyhow::Result<Algorithm> {
+    let algorithm = match jwk.common.key_algorithm {
+        Some(algorithm) => Algorithm::from_str(&algorithm.to_string())
+            .map_err(|_| anyhow!("the key's {} algorithm doesn't sign tokens", algorithm))?,
+        None => {
+            let signs = match &jwk.algorithm {
+                AlgorithmParameters::RSA(_) => matches!(
+                    token,
+                    Algorithm::RS256
+                        | Algorithm::RS384
+                        | Algorithm::RS512
+                        | Algorithm::PS256
+                        | Algorithm::PS384
+                        | Algorithm::PS512
+                ),
+                AlgorithmParameters::EllipticCurve(_) => {
+                    matches!(token, Algorithm::ES256 | Algorithm::ES384)
+                }
+                AlgorithmParameters::OctetKey(_) => {
+                    matches!(
+                        token,
+                        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+                    )
+                }
+                AlgorithmParameters::OctetKeyPair(_) => token == Algorithm::EdDSA,
+            };
+
+            match signs {
+                true => token,
+                false => bail!("the key can't sign {:?} tokens", token),
+            }
+        }
+    };
+
+    if token != algorithm {
+        bail!(
+            "expected a token signed with {:?}, got {:?}",
+            algorithm,
+            token
+        );
+    }
+
+    Ok(algorithm)
+}
+
+/// The name of the bearer security scheme declared for `--auth-token`.
+pub const BEARER_SCHEME: &str = "bearerAuth";
+
+/// Middleware rejecting the requests without the `Authorization: Bearer <token>` header of a
+/// static token with a 401.
+pub struct BearerAuth {
+    token: Rc<str>,
+}
+
+impl BearerAuth {
+    pub fn new(token: &str) -> Self {
+        Self {
+            token: Rc::from(token),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for BearerAuth
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Transform = BearerAuthMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(BearerAuthMiddleware {
+            service,
+            token: self.token.clone(),
+        }))
+    }
+}
+
+pub struct BearerAuthMiddleware<S> {
+    service: S,
+    token: Rc<str>,
+}
+
+impl<S> BearerAuthMiddleware<S> {
+    fn authorize(&self, request: &HttpRequest) -> Result<(), ApiError> {
+        let token = request
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| anyhow!("missing bearer token"));
+
+        token
+            .and_then(|token| match constant_time_eq(token.trim(), &self.token) {
+                true => Ok(()),
+                false => Err(anyhow!("invalid token")),
+            })
+            .map_err(|e| {
+                ApiError::new(StatusCode::UNAUTHORIZED, e)
+                    .header((WWW_AUTHENTICATE, "Bearer error=\"invalid_token\""))
+            })
+    }
+}
+
+impl<S, B> Service<ServiceRequest> for BearerAuthMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, request: ServiceRequest) -> Self::Future {
+        if let Err(e) = self.authorize(request.request()) {
+            let response = e.respond_to(request.request());
+
+            return Box::pin(ready(Ok(request
+                .into_response(response)
+                .map_into_right_body())));
+        }
+
+        let response = self.service.call(request);
+
+        Box::pin(async move { response.await.map(ServiceResponse::map_into_left_body) })
+    }
+}
+
+/// Compare the tokens in a time independent of the position of their first difference.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use actix_web::test::{call_service, init_service, TestRequest};
+    use actix_web::{web, App, HttpResponse};
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde_json::json;
+
+    use super::*;
+
+    const SECRET: &[u8] = b"a secret of the tests";
+
+    /// A key set of a single HMAC key, whose `k` is the base64url encoding of `SECRET`.
+    fn validator(alg: Option<&str>) -> JwtValidator {
+        let mut key = json!({
+            "kty": "oct",
+            "kid": "test",
+            "k": "YSBzZWNyZXQgb2YgdGhlIHRlc3Rz",
+        });
+        if let Some(alg) = alg {
+            key["alg"] = json!(alg);
+        }
+
+        JwtValidator {
+            jwks: serde_json::from_value(json!({ "keys": [key] })).unwrap(),
+            audience: Some(String::from("api")),
+            issuer: None,
+        }
+    }
+
+    fn token(algorithm: Algorithm, expires_in: i64, audience: &str) -> String {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let mut header = Header::new(algorithm);
+        header.kid = Some(String::from("test"));
+
+        encode(
+            &header,
+            &json!({ "sub": "me", "aud": audience, "exp": now + expires_in }),
+            &EncodingKey::from_secret(SECRET),
+        )
+        .unwrap()
+    }
+
+    /// The status of a request authorized like the endpoints are.
+    async fn status(validator: JwtValidator, token: Option<&str>) -> StatusCode {
+        let validator = web::Data::new(validator);
+        let app = init_service(App::new().app_data(validator).route(
+            "/add",
+            web::post().to(
+                |request: HttpRequest, validator: web::Data<JwtValidator>| async move {
+                    match validator.authorize(&request) {
+                        Ok(()) => HttpResponse::Ok().finish(),
+                        Err(e) => e.respond_to(&request),
+                    }
+                },
+            ),
+        ))
+        .await;
+        let mut request = TestRequest::post().uri("/add");
+        if let Some(token) = token {
+            request = request.insert_header((AUTHORIZATION, format!("Bearer {}", token)));
+        }
+
+        call_service(&app, request.to_request()).await.status()
+    }
+
+    #[actix_web::test]
+    async fn authorizes_the_valid_tokens_only() {
+        let valid = token(Algorithm::HS256, 3600, "api");
+        let expired = token(Algorithm::HS256, -3600, "api");
+
+        assert_eq!(
+            status(validator(Some("HS256")), Some(&valid)).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(validator(Some("HS256")), Some(&expired)).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(
+                validator(Some("HS256")),
+                Some(&token(Algorithm::HS256, 3600, "other"))
+            )
+            .await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(validator(Some("HS256")), None).await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[test]
+    fn pins_the_algorithm_of_the_key() {
+        let hs384 = token(Algorithm::HS384, 3600, "api");
+
+        assert_eq!(
+            validator(Some("HS256"))
+                .validate(&hs384)
+                .unwrap_err()
+                .to_string(),
+            "expected a token signed with HS256, got HS384"
+        );
+        // Without an algorithm, the key signs those of its type only
+        assert!(validator(None).validate(&hs384).is_ok());
+        assert_eq!(
+            algorithm(&validator(None).jwks.keys[0], Algorithm::RS256)
+                .unwrap_err()
+                .to_string(),
+            "the key can't sign RS256 tokens"
+        );
+    }
+}
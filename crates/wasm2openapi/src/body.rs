@@ -0,0 +1,386 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use actix_web::dev::Payload;
+use actix_web::error::{JsonPayloadError, QueryPayloadError};
+use actix_web::http::header::{CONTENT_LENGTH, TRANSFER_ENCODING};
+use actix_web::http::StatusCode;
+use actix_web::{web, FromRequest, HttpRequest};
+use serde::de::{DeserializeSeed, Error, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Number, Value};
+
+use crate::error::ApiError;
+
+/// The named parameters of a request body, along with the keys that were given more than once.
+///
+/// Like serde_json, the last value of a duplicated key is kept.
+#[derive(Debug, Default)]
+pub struct Body {
+    pub params: HashMap<String, Value>,
+    /// The parameters given by position, as an array rather than an object, to be named after
+    /// the parameters of the function.
+    pub positional: Option<Vec<Value>>,
+    /// The paths of the duplicated keys, e.g. `p` or `p.x`.
+    pub duplicates: Vec<String>,
+}
+
+impl Body {
+    /// The named parameters of a query string, e.g. `x=1&y=2`, whose values are parsed as JSON
+    /// except for the `strings` parameters, which are taken as is.
+    pub fn from_query(query: &str, strings: &[&str]) -> Result<Self, QueryPayloadError> {
+        let pairs = web::Query::<Vec<(String, String)>>::from_query(query)?.into_inner();
+        let mut body = Self::default();
+
+        for (name, value) in pairs {
+            let value = match strings.contains(&name.as_str()) {
+                true => Value::String(value),
+                false => serde_json::from_str(&value).unwrap_or(Value::String(value)),
+            };
+
+            if body.params.insert(name.clone(), value).is_some() {
+                body.duplicates.push(name);
+            }
+        }
+
+        Ok(body)
+    }
+}
+
+impl<'de> Deserialize<'de> for Body {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut duplicates = vec![];
+        let params = deserializer.deserialize_any(Collect {
+            path: String::new(),
+            duplicates: &mut duplicates,
+        })?;
+
+        match params {
+            Value::Object(params) => Ok(Self {
+                params: params.into_iter().collect(),
+                positional: None,
+                duplicates,
+            }),
+            Value::Array(values) => Ok(Self {
+                params: HashMap::new(),
+                positional: Some(values),
+                duplicates,
+            }),
+            _ => Err(Error::custom(
+                "expected an object of named parameters or an array of positional ones",
+            )),
+        }
+    }
+}
+
+/// Deserializes a JSON value, collecting the paths of the duplicated object keys.
+struct Collect<'a> {
+    path: String,
+    duplicates: &'a mut Vec<String>,
+}
+
+impl Collect<'_> {
+    fn child(&mut self, segment: &str) -> Collect<'_> {
+        Collect {
+            path: match self.path.is_empty() {
+                true => segment.to_string(),
+                false => format!("{}.{}", self.path, segment),
+            },
+            duplicates: self.duplicates,
+        }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for Collect<'_> {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Collect<'_> {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.path.is_empty() {
+            true => write!(
+                f,
+                "an object of named parameters or an array of positional ones"
+            ),
+            false => write!(f, "a JSON value"),
+        }
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::Number(v.into()))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::Number(v.into()))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
+        Ok(Number::from_f64(v).map_or(Value::Null, Value::Number))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<Value, A::Error> {
+        let mut values = vec![];
+
+        while let Some(value) = seq.next_element_seed(self.child(&values.len().to_string()))? {
+            values.push(value);
+        }
+
+        Ok(Value::Array(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(mut self, mut map: A) -> Result<Value, A::Error> {
+        let mut values = Map::new();
+
+        while let Some(key) = map.next_key::<String>()? {
+            let value = map.next_value_seed(self.child(&key))?;
+
+            if values.insert(key.clone(), value).is_some() {
+                let path = self.child(&key).path;
+                self.duplicates.push(path);
+            }
+        }
+
+        Ok(Value::Object(values))
+    }
+}
+
+/// Read the JSON body of a request, or `None` when it has no body at all, which is only valid for
+/// the functions without parameters.
+pub async fn read(
+    request: &HttpRequest,
+    payload: &mut Payload,
+) -> Result<Option<Body>, actix_web::Error> {
+    match web::Json::<Body>::from_request(request, payload).await {
+        Ok(web::Json(body)) => Ok(Some(body)),
+        Err(_) if is_empty(request) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Whether the request has no body at all, which JSON parsing would otherwise reject.
+fn is_empty(request: &HttpRequest) -> bool {
+    let headers = request.headers();
+
+    match headers.get(CONTENT_LENGTH) {
+        Some(length) => length == "0",
+        None => !headers.contains_key(TRANSFER_ENCODING),
+    }
+}
+
+/// The error of a JSON request body which can't be read, as a 413 when it is larger than the limit.
+pub fn payload_error(error: &JsonPayloadError) -> ApiError {
+    match error {
+        JsonPayloadError::OverflowKnownLength { limit, .. }
+        | JsonPayloadError::Overflow { limit } => too_large(*limit),
+        error => ApiError::bad_request(error),
+    }
+}
+
+pub fn too_large(limit: usize) -> ApiError {
+    ApiError::new(
+        StatusCode::PAYLOAD_TOO_LARGE,
+        format!(
+            "the request body is larger than the limit of {} bytes",
+            limit
+        ),
+    )
+    .detail("limit", limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::header;
+    use actix_web::test::TestRequest;
+    use actix_web::FromRequest;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{defaults, testing};
+
+    /// The request body read like the server does, with the given limit.
+    async fn extract(request: TestRequest, limit: usize) -> Result<Body, ApiError> {
+        let (request, mut payload) = request
+            .app_data(web::JsonConfig::default().limit(limit))
+            .to_http_parts();
+
+        web::Json::<Body>::from_request(&request, &mut payload)
+            .await
+            .map(web::Json::into_inner)
+            .map_err(|e| payload_error(e.as_error::<JsonPayloadError>().unwrap()))
+    }
+
+    fn json(body: &'static str) -> TestRequest {
+        TestRequest::post()
+            .insert_header(header::ContentType::json())
+            .set_payload(body)
+    }
+
+    #[actix_web::test]
+    async fn reads_the_named_and_positional_parameters() {
+        let body = extract(json(r#"{ "x": 1, "p": { "y": 2, "y": 3 }, "x": 4 }"#), 1024)
+            .await
+            .unwrap();
+        assert_eq!(body.params["x"], json!(4));
+        assert_eq!(body.params["p"], json!({ "y": 3 }));
+        assert_eq!(body.positional, None);
+        assert_eq!(body.duplicates, ["p.y", "x"]);
+
+        let body = extract(json(r#"[1, { "a": 1, "a": 2 }]"#), 1024)
+            .await
+            .unwrap();
+        assert!(body.params.is_empty());
+        assert_eq!(body.positional, Some(vec![json!(1), json!({ "a": 2 })]));
+        assert_eq!(body.duplicates, ["1.a"]);
+
+        let error = extract(json("1"), 1024).await.unwrap_err();
+        assert_eq!(error.status, StatusCode::BAD_REQUEST);
+        assert!(error
+            .message
+            .contains("expected an object of named parameters or an array of positional ones"));
+    }
+
+    #[actix_web::test]
+    async fn rejects_the_bodies_which_are_not_json() {
+        let error = extract(
+            TestRequest::post()
+                .insert_header(header::ContentType::plaintext())
+                .set_payload("{}"),
+            1024,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.status, StatusCode::BAD_REQUEST);
+        assert_eq!(error.message, JsonPayloadError::ContentType.to_string());
+
+        let error = extract(json("{"), 1024).await.unwrap_err();
+        assert_eq!(error.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn rejects_the_bodies_over_the_limit() {
+        let body = r#"{ "x": "0123456789" }"#;
+
+        assert!(extract(json(body), body.len()).await.is_ok());
+
+        let error = extract(json(body), body.len() - 1).await.unwrap_err();
+        assert_eq!(error.status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(
+            error.message,
+            format!(
+                "the request body is larger than the limit of {} bytes",
+                body.len() - 1
+            )
+        );
+        assert_eq!(error.details["limit"], json!(body.len() - 1));
+
+        // Without a length, the limit is only found while reading the body
+        let error = payload_error(&JsonPayloadError::Overflow { limit: 8 });
+        assert_eq!(error.status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(error.details["limit"], json!(8));
+    }
+
+    #[test]
+    fn tells_the_requests_without_a_body() {
+        let empty = |request: TestRequest| is_empty(&request.to_http_request());
+
+        assert!(empty(TestRequest::post()));
+        assert!(empty(
+            TestRequest::post().insert_header((CONTENT_LENGTH, "0"))
+        ));
+        assert!(!empty(
+            TestRequest::post().insert_header((CONTENT_LENGTH, "2"))
+        ));
+        assert!(!empty(
+            TestRequest::post().insert_header((TRANSFER_ENCODING, "chunked"))
+        ));
+    }
+
+    #[test]
+    fn reads_the_query_strings() {
+        let body = Body::from_query("x=1&s=1&t=%7B%7D&u=abc&x=2", &["s"]).unwrap();
+
+        assert_eq!(body.params["x"], json!(2));
+        assert_eq!(body.params["s"], json!("1"));
+        assert_eq!(body.params["t"], json!({}));
+        assert_eq!(body.params["u"], json!("abc"));
+        assert_eq!(body.duplicates, ["x"]);
+    }
+
+    #[actix_web::test]
+    async fn calls_the_functions_without_parameters_without_a_body() {
+        let wit = r#"
+            package test:counter;
+
+            world counter {
+                /// @method post
+                export count: func() -> u32;
+                export add: func(x: s32, y: s32) -> s32;
+            }
+        "#;
+        let component = testing::component_with(
+            wit,
+            r#"
+                (module
+                    (func (export "count") (result i32) (i32.const 42))
+                    (func (export "add") (param i32 i32) (result i32)
+                        (i32.add (local.get 0) (local.get 1))))
+            "#,
+        );
+        let endpoints = testing::endpoints_of(&component, wit, defaults());
+
+        for request in [
+            TestRequest::post(),
+            TestRequest::post()
+                .insert_header(header::ContentType::json())
+                .insert_header((header::CONTENT_LENGTH, 0)),
+        ] {
+            let (request, mut payload) = request.to_http_parts();
+            let body = read(&request, &mut payload).await.unwrap();
+            assert!(body.is_none());
+
+            let response = endpoints[0].call(&request, body, false).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let result = actix_web::body::to_bytes(response.into_body())
+                .await
+                .unwrap();
+            assert_eq!(result, "42");
+
+            let error = endpoints[1].call(&request, None, false).await.unwrap_err();
+            assert_eq!(
+                error.message,
+                "expected a JSON object of named parameters, got an empty body"
+            );
+        }
+
+        let (request, mut payload) = json("{")
+            .insert_header((header::CONTENT_LENGTH, 1))
+            .to_http_parts();
+        assert!(read(&request, &mut payload).await.is_err());
+    }
+}
@@ -0,0 +1,76 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use wasmtime::component::Component;
+use wasmtime::Engine;
+
+use crate::source::ComponentSource;
+
+/// Compile a component, or load it from the cache of its compiled artifact kept next to its file,
+/// refreshing the cache when missing or stale.
+///
+/// The components downloaded or read from the standard input are always compiled.
+pub fn load(
+    engine: &Engine,
+    data: &[u8],
+    source: &ComponentSource,
+    cache: bool,
+) -> anyhow::Result<Component> {
+    let ComponentSource::Path(path) = source else {
+        return compile(engine, data);
+    };
+    if !cache {
+        return compile(engine, data);
+    }
+
+    let cached = cache_path(engine, data, path);
+    if cached.exists() {
+        // SAFETY: the artifact was serialized by this same engine configuration, as its name is
+        // keyed on it, and is trusted as much as the component it is next to
+        match unsafe { Component::deserialize_file(engine, &cached) } {
+            Ok(component) => {
+                log::debug!("Loaded the compiled component from {}", cached.display());
+
+                return Ok(component);
+            }
+            Err(e) => log::warn!(
+                "Failed to load the compiled component from {}, recompiling it: {}",
+                cached.display(),
+                e
+            ),
+        }
+    }
+
+    let component = compile(engine, data)?;
+    if let Err(e) = component
+        .serialize()
+        .and_then(|artifact| Ok(std::fs::write(&cached, artifact)?))
+    {
+        log::warn!(
+            "Failed to cache the compiled component to {}: {}",
+            cached.display(),
+            e
+        );
+    }
+
+    Ok(component)
+}
+
+fn compile(engine: &Engine, data: &[u8]) -> anyhow::Result<Component> {
+    Component::from_binary(engine, data).context("Failed to load component")
+}
+
+/// The path of the compiled artifact of a component, keyed on a hash of its bytes and of the
+/// engine's version and configuration (e.g. `add.wasm.0123456789abcdef.cwasm` for `add.wasm`).
+fn cache_path(engine: &Engine, data: &[u8], path: &Path) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    engine.precompile_compatibility_hash().hash(&mut hasher);
+
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{:016x}.cwasm", hasher.finish()));
+
+    path.with_file_name(name)
+}
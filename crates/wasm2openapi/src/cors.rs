@@ -0,0 +1,92 @@
+use actix_cors::Cors;
+use actix_web::http::header::HeaderName;
+use actix_web::http::{Method, Uri};
+use anyhow::bail;
+use clap::Args;
+
+/// Options of the Cross-Origin Resource Sharing of the served API, which is disabled by default.
+#[derive(Args, Debug, Clone)]
+pub struct CorsOptions {
+    /// Allow the cross-origin requests from this origin (e.g. `https://example.com`), or from any
+    /// origin with `*`
+    #[clap(long, value_parser = parse_origin)]
+    cors_allow_origin: Vec<String>,
+
+    /// Allow the cross-origin requests with this method [default: GET, POST]
+    #[clap(long, requires = "cors_allow_origin")]
+    cors_allow_method: Vec<Method>,
+
+    /// Allow the cross-origin requests with this header [default: any header]
+    #[clap(long, requires = "cors_allow_origin")]
+    cors_allow_header: Vec<HeaderName>,
+}
+
+impl CorsOptions {
+    pub fn enabled(&self) -> bool {
+        !self.cors_allow_origin.is_empty()
+    }
+
+    /// The middleware answering the preflight requests and adding the CORS headers.
+    pub fn middleware(&self) -> Cors {
+        let mut cors = Cors::default();
+
+        for origin in &self.cors_allow_origin {
+            cors = match origin.as_str() {
+                "*" => cors.allow_any_origin().send_wildcard(),
+                origin => cors.allowed_origin(origin),
+            };
+        }
+
+        cors = match self.cors_allow_method.is_empty() {
+            true => cors.allowed_methods([Method::GET, Method::POST]),
+            false => cors.allowed_methods(self.cors_allow_method.clone()),
+        };
+
+        match self.cors_allow_header.is_empty() {
+            true => cors.allow_any_header(),
+            false => cors.allowed_headers(self.cors_allow_header.clone()),
+        }
+    }
+}
+
+/// An origin as sent by the browsers, `<scheme>://<host>[:<port>]`, checked here since the
+/// middleware would otherwise panic in every worker.
+fn parse_origin(s: &str) -> anyhow::Result<String> {
+    if s == "*" {
+        return Ok(s.to_string());
+    }
+
+    let origin = s
+        .parse::<Uri>()
+        .ok()
+        .and_then(|uri| Some(format!("{}://{}", uri.scheme_str()?, uri.authority()?)));
+
+    match origin {
+        Some(origin) if origin == s => Ok(origin),
+        _ => bail!(
+            "expected `*` or an origin, e.g. `https://example.com` or `http://localhost:8080`"
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_origins() {
+        for origin in ["*", "https://example.com", "http://localhost:8080"] {
+            assert_eq!(parse_origin(origin).unwrap(), origin);
+        }
+        for origin in [
+            "",
+            "example.com",
+            "https://example.com/",
+            "https://example.com/app",
+            "https://exa mple.com",
+            "https://",
+        ] {
+            assert!(parse_origin(origin).is_err(), "{:?} was accepted", origin);
+        }
+    }
+}
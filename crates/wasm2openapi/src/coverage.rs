@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+
+use serde_json::json;
+use wit_parser::Resolve;
+
+use crate::{ExportedFunction, PathOptions, Type};
+
+/// Report how many of the functions exported by the components only use types that are fully
+/// supported, both by the schema generation and the JSON conversions.
+pub fn report(
+    components: &[(&Resolve, &[ExportedFunction])],
+    options: &PathOptions,
+) -> serde_json::Value {
+    let mut kinds = BTreeMap::<&str, (usize, usize)>::new();
+    let mut unsupported_functions = vec![];
+
+    let functions = components
+        .iter()
+        .flat_map(|(resolve, functions)| functions.iter().map(move |exported| (*resolve, exported)))
+        .collect::<Vec<_>>();
+
+    for (resolve, exported) in &functions {
+        let types = exported
+            .function
+            .params
+            .iter()
+            .map(|(_, ty)| *ty)
+            .chain(exported.function.results.iter_types().copied())
+            .map(Type);
+        let mut unsupported_types = vec![];
+
+        for ty in types {
+            let kind = ty.kind(resolve);
+            let (supported, unsupported) = kinds.entry(kind).or_default();
+
+            if ty.is_supported(resolve) {
+                *supported += 1;
+            } else {
+                *unsupported += 1;
+                unsupported_types.push(kind);
+            }
+        }
+
+        if !unsupported_types.is_empty() {
+            unsupported_types.sort();
+            unsupported_types.dedup();
+            unsupported_functions.push(json!({
+                "path": exported.path(options),
+                "types": unsupported_types,
+            }));
+        }
+    }
+
+    let total = functions.len();
+    let supported = total - unsupported_functions.len();
+    let percentage = match total {
+        0 => 100.0,
+        total => supported as f64 * 100.0 / total as f64,
+    };
+
+    json!({
+        "functions": {
+            "total": total,
+            "supported": supported,
+            "unsupported": total - supported,
+            "percentage": percentage,
+        },
+        "kinds": kinds
+            .into_iter()
+            .map(|(kind, (supported, unsupported))| {
+                (kind, json!({ "supported": supported, "unsupported": unsupported }))
+            })
+            .collect::<BTreeMap<_, _>>(),
+        "unsupported_functions": unsupported_functions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{defaults, list_wasm_component_functions, testing};
+
+    #[test]
+    fn counts_the_supported_functions() {
+        let component = testing::component(
+            r#"
+                package test:counters;
+
+                interface types {
+                    resource counter;
+                    record point { x: s32, y: s32 }
+                }
+
+                world counters {
+                    use types.{counter, point};
+
+                    export add: func(x: s32, y: s32) -> s32;
+                    export move: func(point: point) -> point;
+                    export increment: func(counter: borrow<counter>) -> u32;
+                }
+            "#,
+        );
+        let decoded = wit_component::decode(&component).unwrap();
+        let functions = list_wasm_component_functions(&decoded, None, None);
+
+        let report = report(&[(decoded.resolve(), &functions)], &defaults());
+
+        assert_eq!(
+            report["functions"],
+            json!({ "total": 3, "supported": 2, "unsupported": 1, "percentage": 200.0 / 3.0 })
+        );
+        assert_eq!(
+            report["unsupported_functions"],
+            json!([{ "path": "/increment", "types": ["handle"] }])
+        );
+        assert_eq!(
+            report["kinds"]["s32"],
+            json!({ "supported": 3, "unsupported": 0 })
+        );
+    }
+}
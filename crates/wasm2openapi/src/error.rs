@@ -0,0 +1,141 @@
+use actix_web::http::header::{Accept, ContentType, Header, HeaderMap, TryIntoHeaderPair};
+use actix_web::http::StatusCode;
+use actix_web::{HttpRequest, HttpResponse};
+use serde_json::{json, Map, Value};
+
+/// An error returned to the API clients, as a JSON envelope or as plain text when the client
+/// prefers `text/plain`.
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub message: String,
+    pub headers: HeaderMap,
+    /// The additional members of the JSON envelope, e.g. the names of the invalid parameters.
+    pub details: Map<String, Value>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, message: impl ToString) -> Self {
+        Self {
+            status,
+            message: message.to_string(),
+            headers: HeaderMap::new(),
+            details: Map::new(),
+        }
+    }
+
+    pub fn bad_request(message: impl ToString) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, message)
+    }
+
+    pub fn header(mut self, header: impl TryIntoHeaderPair) -> Self {
+        if let Ok((name, value)) = header.try_into_pair() {
+            self.headers.insert(name, value);
+        }
+
+        self
+    }
+
+    pub fn detail(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.details.insert(String::from(name), value.into());
+
+        self
+    }
+
+    pub fn respond_to(&self, request: &HttpRequest) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status);
+
+        for (name, value) in self.headers.iter() {
+            response.insert_header((name.clone(), value.clone()));
+        }
+
+        if prefers_plain_text(request) {
+            response
+                .content_type(ContentType::plaintext())
+                .body(self.message.clone())
+        } else {
+            let mut envelope = self.details.clone();
+
+            envelope.insert(String::from("error"), json!(self.message));
+
+            response.content_type(ContentType::json()).json(envelope)
+        }
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Whether `text/plain` is ranked before JSON in the request's `Accept` header.
+fn prefers_plain_text(request: &HttpRequest) -> bool {
+    let Ok(accept) = Accept::parse(request) else {
+        return false;
+    };
+
+    accept
+        .ranked()
+        .into_iter()
+        .find_map(|mime| match mime.essence_str() {
+            "text/plain" | "text/*" => Some(true),
+            "application/json" | "application/*" | "*/*" => Some(false),
+            _ => None,
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::header::{self, HeaderValue};
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    async fn respond(error: &ApiError, accept: Option<&str>) -> (HttpResponse<()>, String) {
+        let request = match accept {
+            Some(accept) => TestRequest::default().insert_header((header::ACCEPT, accept)),
+            None => TestRequest::default(),
+        };
+        let response = error.respond_to(&request.to_http_request());
+        let (response, body) = response.into_parts();
+        let body = actix_web::body::to_bytes(body).await.unwrap();
+
+        (response, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[actix_web::test]
+    async fn negotiates_the_content_type() {
+        let error = ApiError::bad_request("missing parameter `x`")
+            .detail("parameters", json!(["x"]))
+            .header(("x-fuel-consumed", "12"));
+        let content_type =
+            |response: &HttpResponse<()>| response.headers().get(header::CONTENT_TYPE).cloned();
+
+        let (response, body) = respond(&error, Some("text/plain")).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            content_type(&response),
+            Some(HeaderValue::from_static("text/plain; charset=utf-8"))
+        );
+        assert_eq!(response.headers().get("x-fuel-consumed").unwrap(), "12");
+        assert_eq!(body, "missing parameter `x`");
+
+        for accept in [
+            None,
+            Some("application/json, text/plain;q=0.5"),
+            Some("image/png"),
+        ] {
+            let (response, body) = respond(&error, accept).await;
+            assert_eq!(
+                content_type(&response),
+                Some(HeaderValue::from_static("application/json"))
+            );
+            assert_eq!(
+                serde_json::from_str::<Value>(&body).unwrap(),
+                json!({ "error": "missing parameter `x`", "parameters": ["x"] })
+            );
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use clap::ValueEnum;
+use serde_json::{json, Value};
+
+/// How optional values are rendered in the generated examples.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OptionExample {
+    /// Render the value of the `some` case
+    #[default]
+    Some,
+    /// Render `null`
+    Null,
+}
+
+/// Builds deterministic example values validating against the JSON schemas of an OpenAPI document.
+pub struct Examples<'a> {
+    spec: &'a Value,
+    options: OptionExample,
+}
+
+impl<'a> Examples<'a> {
+    pub fn new(spec: &'a Value, options: OptionExample) -> Self {
+        Self { spec, options }
+    }
+
+    pub fn example(&self, schema: &Value) -> Value {
+        self.build(schema, &mut vec![])
+    }
+
+    /// `refs` holds the references being expanded, to stop on recursive types.
+    fn build(&self, schema: &Value, refs: &mut Vec<String>) -> Value {
+        if let Some(example) = schema.get("example") {
+            return example.clone();
+        }
+
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            if refs.iter().any(|r| r == reference) {
+                return Value::Null;
+            }
+
+            let Some(target) = reference
+                .strip_prefix('#')
+                .and_then(|pointer| self.spec.pointer(pointer))
+            else {
+                return Value::Null;
+            };
+
+            refs.push(reference.to_string());
+            let example = self.build(target, refs);
+            refs.pop();
+
+            return example;
+        }
+
+        if self.options == OptionExample::Null && is_nullable(schema) {
+            return Value::Null;
+        }
+
+        if let Some(first) = schema.get("enum").and_then(|cases| cases.get(0)) {
+            return first.clone();
+        }
+
+        for composition in ["oneOf", "anyOf"] {
+            if let Some(first) = schema.get(composition).and_then(|schemas| schemas.get(0)) {
+                return self.build(first, refs);
+            }
+        }
+
+        if let Some(schemas) = schema.get("allOf").and_then(Value::as_array) {
+            return schemas
+                .iter()
+                .map(|schema| self.build(schema, refs))
+                .reduce(|merged, example| match (merged, example) {
+                    (Value::Object(mut merged), Value::Object(example)) => {
+                        merged.extend(example);
+                        Value::Object(merged)
+                    }
+                    (_, example) => example,
+                })
+                .unwrap_or(Value::Null);
+        }
+
+        let ty = match schema.get("type") {
+            Some(Value::Array(types)) => types
+                .iter()
+                .filter_map(Value::as_str)
+                .find(|ty| *ty != "null"),
+            ty => ty.and_then(Value::as_str),
+        };
+
+        match ty {
+            Some("object") => Value::Object(
+                schema
+                    .get("properties")
+                    .and_then(Value::as_object)
+                    .map(|properties| {
+                        properties
+                            .iter()
+                            .map(|(name, schema)| (name.clone(), self.build(schema, refs)))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            ),
+            Some("array") => match tuple_items(schema) {
+                Some(items) => items.iter().map(|item| self.build(item, refs)).collect(),
+                None => {
+                    let count = schema.get("minItems").and_then(Value::as_u64).unwrap_or(1);
+                    let item = schema
+                        .get("items")
+                        .map(|items| self.build(items, refs))
+                        .unwrap_or(Value::Null);
+
+                    Value::Array(vec![item; count as usize])
+                }
+            },
+            Some("string") => {
+                let length = schema.get("minLength").and_then(Value::as_u64).unwrap_or(0);
+
+                json!("a".repeat(length as usize))
+            }
+            Some("integer") => schema.get("minimum").cloned().unwrap_or(json!(0)),
+            Some("number") => schema.get("minimum").cloned().unwrap_or(json!(0.0)),
+            Some("boolean") => json!(false),
+            _ => Value::Null,
+        }
+    }
+}
+
+/// The schemas of the elements of a tuple, by position: its `prefixItems` in OpenAPI 3.1, or in
+/// 3.0 the alternatives of its `items`, one per element when there are as many as its arity.
+fn tuple_items(schema: &Value) -> Option<&Vec<Value>> {
+    if let Some(items) = schema.get("prefixItems").and_then(Value::as_array) {
+        return Some(items);
+    }
+
+    let items = schema
+        .get("items")
+        .and_then(|items| items.get("anyOf"))
+        .and_then(Value::as_array)?;
+    let arity = |keyword| schema.get(keyword).and_then(Value::as_u64);
+
+    match (arity("minItems"), arity("maxItems")) {
+        (Some(min), Some(max)) if min == max && min == items.len() as u64 => Some(items),
+        _ => None,
+    }
+}
+
+fn is_nullable(schema: &Value) -> bool {
+    schema.get("nullable").and_then(Value::as_bool) == Some(true)
+        || schema
+            .get("type")
+            .and_then(Value::as_array)
+            .is_some_and(|types| types.iter().any(|ty| ty == "null"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example(schema: Value) -> Value {
+        let spec = json!({});
+
+        Examples::new(&spec, OptionExample::Some).example(&schema)
+    }
+
+    #[test]
+    fn builds_the_tuples_by_position() {
+        let tuple = json!({
+            "type": "array",
+            "items": { "anyOf": [{ "type": "integer" }, { "type": "string", "minLength": 1 }] },
+            "minItems": 2,
+            "maxItems": 2,
+        });
+        assert_eq!(example(tuple), json!([0, "a"]));
+
+        let tuple = json!({
+            "type": "array",
+            "prefixItems": [{ "type": "boolean" }, { "type": "integer", "minimum": 1 }],
+        });
+        assert_eq!(example(tuple), json!([false, 1]));
+    }
+
+    #[test]
+    fn repeats_the_items_of_the_lists() {
+        let list = json!({ "type": "array", "items": { "type": "integer" } });
+        assert_eq!(example(list), json!([0]));
+
+        // Not a tuple, since its length isn't fixed
+        let list = json!({
+            "type": "array",
+            "items": { "anyOf": [{ "type": "integer" }, { "type": "string" }] },
+            "minItems": 2,
+        });
+        assert_eq!(example(list), json!([0, 0]));
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use actix_web::{web, HttpRequest};
+use clap::ValueEnum;
+use serde_json::Value;
+use wasmtime::component::Type;
+
+use crate::error::ApiError;
+
+/// The query parameter listing the record fields to return.
+pub const FIELDS: &str = "fields";
+
+/// How the unknown names of the `fields` query parameter are handled.
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum FieldSelection {
+    /// Ignore the unknown field names
+    #[default]
+    Ignore,
+    /// Respond with a 400 error
+    Reject,
+}
+
+/// The field names of the `fields` query parameter of the request, if any.
+pub fn requested(request: &HttpRequest) -> Option<Vec<String>> {
+    let query = web::Query::<HashMap<String, String>>::from_query(request.query_string()).ok()?;
+
+    query.get(FIELDS).map(|fields| {
+        fields
+            .split(',')
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .map(String::from)
+            .collect()
+    })
+}
+
+/// Only keep the given fields of a `record` or `list<record>` result of type `ty`.
+///
+/// The fields are left untouched when the result has no record to select the fields of.
+pub fn select(
+    value: &mut Value,
+    ty: &Type,
+    fields: &[String],
+    policy: FieldSelection,
+) -> Result<(), ApiError> {
+    let record = match ty {
+        Type::Record(record) => record.clone(),
+        Type::List(list) => match list.ty() {
+            Type::Record(record) => record,
+            _ => return Ok(()),
+        },
+        _ => return Ok(()),
+    };
+
+    if let FieldSelection::Reject = policy {
+        if let Some(unknown) = fields
+            .iter()
+            .find(|field| !record.fields().any(|f| f.name == field.as_str()))
+        {
+            return Err(ApiError::bad_request(format!(
+                "unknown field `{}`",
+                unknown
+            )));
+        }
+    }
+
+    let retain = |value: &mut Value| {
+        if let Value::Object(object) = value {
+            object.retain(|name, _| fields.contains(name));
+        }
+    };
+
+    match value {
+        Value::Array(values) => values.iter_mut().for_each(retain),
+        value => retain(value),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing;
+
+    const WIT: &str = r#"
+        package test:people;
+
+        interface types {
+            record address { city: string, zip: string }
+            record person { name: string, age: u8, address: address }
+        }
+
+        world people {
+            use types.{person};
+
+            export f: func(person: person, people: list<person>, age: u8);
+        }
+    "#;
+
+    fn fields(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_the_requested_fields() {
+        let request = actix_web::test::TestRequest::get()
+            .uri("/f?fields=name,%20age,,&x=1")
+            .to_http_request();
+
+        assert_eq!(requested(&request), Some(fields(&["name", "age"])));
+        assert_eq!(
+            requested(&actix_web::test::TestRequest::get().to_http_request()),
+            None
+        );
+    }
+
+    #[test]
+    fn selects_the_fields_of_the_records() {
+        let types = testing::param_types(WIT, "f");
+        let person = json!({
+            "name": "Ada",
+            "age": 36,
+            "address": { "city": "London", "zip": "W1" },
+        });
+
+        let mut value = person.clone();
+        select(
+            &mut value,
+            &types[0],
+            &fields(&["name", "address"]),
+            FieldSelection::Ignore,
+        )
+        .unwrap();
+        assert_eq!(
+            value,
+            json!({ "name": "Ada", "address": { "city": "London", "zip": "W1" } })
+        );
+
+        let mut value = json!([person.clone(), person.clone()]);
+        select(
+            &mut value,
+            &types[1],
+            &fields(&["age"]),
+            FieldSelection::Ignore,
+        )
+        .unwrap();
+        assert_eq!(value, json!([{ "age": 36 }, { "age": 36 }]));
+
+        let mut value = json!(36);
+        select(
+            &mut value,
+            &types[2],
+            &fields(&["age"]),
+            FieldSelection::Reject,
+        )
+        .unwrap();
+        assert_eq!(value, json!(36));
+    }
+
+    #[test]
+    fn handles_the_unknown_fields_by_policy() {
+        let types = testing::param_types(WIT, "f");
+        let person = json!({
+            "name": "Ada",
+            "age": 36,
+            "address": { "city": "London", "zip": "W1" },
+        });
+        let requested = fields(&["name", "city"]);
+
+        let mut value = person.clone();
+        select(&mut value, &types[0], &requested, FieldSelection::Ignore).unwrap();
+        assert_eq!(value, json!({ "name": "Ada" }));
+
+        let mut value = person.clone();
+        let error = select(&mut value, &types[0], &requested, FieldSelection::Reject).unwrap_err();
+        assert_eq!(error.status, actix_web::http::StatusCode::BAD_REQUEST);
+        assert_eq!(error.message, "unknown field `city`");
+        assert_eq!(value, person);
+    }
+}
@@ -0,0 +1,112 @@
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::http::StatusCode;
+use actix_web::HttpRequest;
+
+use crate::error::ApiError;
+
+/// The request header a client lowers the fuel budget of its call with.
+pub const FUEL_BUDGET: HeaderName = HeaderName::from_static("x-fuel-budget");
+/// The response header reporting the fuel consumed by the call.
+pub const FUEL_CONSUMED: HeaderName = HeaderName::from_static("x-fuel-consumed");
+
+/// The fuel budget of a call: the server maximum, lowered by the `X-Fuel-Budget` header if any.
+pub fn budget(request: &HttpRequest, max: u64) -> Result<u64, ApiError> {
+    let Some(value) = request.headers().get(FUEL_BUDGET) else {
+        return Ok(max);
+    };
+
+    value
+        .to_str()
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(|budget| budget.min(max))
+        .ok_or_else(|| ApiError::bad_request(format!("invalid {} header", FUEL_BUDGET)))
+}
+
+pub fn consumed_header(consumed: u64) -> (HeaderName, HeaderValue) {
+    (FUEL_CONSUMED, HeaderValue::from(consumed))
+}
+
+pub fn out_of_fuel() -> ApiError {
+    ApiError::new(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "the function call ran out of fuel",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::{self, TestServer};
+
+    #[actix_web::test]
+    async fn reports_and_limits_the_fuel_of_the_calls() {
+        let component = testing::component_with(
+            r#"
+                package test:spin;
+
+                world spin {
+                    export spin: func(n: u32) -> u32;
+                }
+            "#,
+            r#"
+                (module
+                    (func (export "spin") (param i32) (result i32)
+                        (local i32)
+                        (block
+                            (loop
+                                (br_if 1 (i32.ge_u (local.get 1) (local.get 0)))
+                                (local.set 1 (i32.add (local.get 1) (i32.const 1)))
+                                (br 0)))
+                        (local.get 1)))
+            "#,
+        );
+        let server = TestServer::start(&component, &["--max-fuel", "1000000"], &[]).await;
+        let spin = |budget: Option<&str>| {
+            let request = reqwest::Client::new()
+                .post(format!("{}/spin", server.url))
+                .json(&json!({ "n": 1000 }));
+
+            match budget {
+                Some(budget) => request.header(FUEL_BUDGET.as_str(), budget),
+                None => request,
+            }
+            .send()
+        };
+        let consumed = |response: &reqwest::Response| {
+            response.headers()[FUEL_CONSUMED.as_str()]
+                .to_str()
+                .unwrap()
+                .parse::<u64>()
+                .unwrap()
+        };
+
+        let response = spin(None).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let spent = consumed(&response);
+        assert!(spent > 1000, "{}", spent);
+        assert_eq!(response.text().await.unwrap(), "1000");
+
+        // The budget is clamped to the maximum of the server
+        let response = spin(Some("1000000000")).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(consumed(&response), spent);
+
+        let response = spin(Some("100")).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(consumed(&response), 100);
+        assert_eq!(
+            response.json::<serde_json::Value>().await.unwrap(),
+            json!({ "error": "the function call ran out of fuel" })
+        );
+
+        let response = spin(Some("a lot")).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        assert_eq!(
+            response.json::<serde_json::Value>().await.unwrap(),
+            json!({ "error": "invalid x-fuel-budget header" })
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use actix_web::http::header::{HeaderName, HeaderValue};
+use anyhow::{anyhow, bail};
+
+/// Headers managed by the server or only meaningful for a single connection.
+const RESERVED_RESPONSE_HEADERS: &[&str] = &[
+    "connection",
+    "content-length",
+    "content-type",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Parse a `<name>: <value>` header.
+pub fn parse_header(s: &str) -> anyhow::Result<(HeaderName, HeaderValue)> {
+    let (name, value) = s
+        .split_once(':')
+        .ok_or_else(|| anyhow!("expected `<name>: <value>`"))?;
+
+    Ok((name.trim().parse()?, value.trim().parse()?))
+}
+
+/// Parse a `<name>: <value>` header that can be added to every response.
+pub fn parse_response_header(s: &str) -> anyhow::Result<(HeaderName, HeaderValue)> {
+    let (name, value) = parse_header(s)?;
+
+    if RESERVED_RESPONSE_HEADERS.contains(&name.as_str()) {
+        bail!("`{}` is a reserved header", name);
+    }
+
+    Ok((name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::{self, TestServer};
+
+    #[test]
+    fn rejects_the_reserved_response_headers() {
+        assert_eq!(
+            parse_response_header(" Cache-Control : no-store ").unwrap(),
+            (
+                HeaderName::from_static("cache-control"),
+                HeaderValue::from_static("no-store")
+            )
+        );
+        assert_eq!(
+            parse_response_header("Transfer-Encoding: chunked")
+                .unwrap_err()
+                .to_string(),
+            "`transfer-encoding` is a reserved header"
+        );
+        assert_eq!(
+            parse_response_header("no-store").unwrap_err().to_string(),
+            "expected `<name>: <value>`"
+        );
+    }
+
+    #[actix_web::test]
+    async fn adds_the_response_headers_to_the_endpoint_responses() {
+        let component = testing::component_with(testing::CALC_WIT, testing::CALC_WAT);
+        let server = TestServer::start(
+            &component,
+            &[],
+            &[
+                "--response-header",
+                "Cache-Control: no-store",
+                "--response-header",
+                "X-Content-Type-Options: nosniff",
+            ],
+        )
+        .await;
+
+        let response = reqwest::Client::new()
+            .post(format!("{}/add", server.url))
+            .json(&json!({ "x": 2, "y": 3 }))
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.headers()["cache-control"], "no-store");
+        assert_eq!(response.headers()["x-content-type-options"], "nosniff");
+        assert_eq!(response.text().await.unwrap(), "5");
+    }
+}
@@ -0,0 +1,17 @@
+use actix_web::HttpResponse;
+use serde_json::json;
+
+/// The path of the liveness probe, answering once the server is up.
+pub const HEALTHZ: &str = "/healthz";
+/// The path of the readiness probe, answering once the components are instantiated.
+pub const READYZ: &str = "/readyz";
+
+pub async fn healthz() -> HttpResponse {
+    HttpResponse::Ok().json(json!({ "status": "ok" }))
+}
+
+/// The components are instantiated before the server starts listening, so it is ready as soon as
+/// it answers.
+pub async fn readyz() -> HttpResponse {
+    HttpResponse::Ok().json(json!({ "status": "ready" }))
+}
@@ -0,0 +1,46 @@
+use anyhow::Context;
+use wasmtime::component::{ComponentType, Instance, Lift};
+use wasmtime::AsContextMut;
+
+/// The name of the function a component exports at its world root to describe its API, as:
+///
+/// ```wit
+/// record info {
+///     title: string,
+///     version: string,
+///     description: string,
+/// }
+///
+/// export openapi-info: func() -> info;
+/// ```
+///
+/// The record may have any name, but its fields must be declared in this order.
+pub const OPENAPI_INFO: &str = "openapi-info";
+
+/// The `info` object of the OpenAPI definition, as returned by the component.
+#[derive(ComponentType, Lift)]
+#[component(record)]
+pub struct Info {
+    pub title: String,
+    pub version: String,
+    pub description: String,
+}
+
+/// Call the `openapi-info` function exported by the component.
+pub fn call(instance: &Instance, mut store: impl AsContextMut) -> anyhow::Result<Info> {
+    let func = instance
+        .get_typed_func::<(), (Info,)>(store.as_context_mut(), OPENAPI_INFO)
+        .with_context(|| {
+            format!(
+                "`{}` must be a `func() -> record {{ title: string, version: string, description: string }}`",
+                OPENAPI_INFO
+            )
+        })?;
+    let (info,) = func
+        .call(store.as_context_mut(), ())
+        .with_context(|| format!("Failed to call `{}`", OPENAPI_INFO))?;
+
+    func.post_return(store.as_context_mut())?;
+
+    Ok(info)
+}
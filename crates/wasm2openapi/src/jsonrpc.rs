@@ -0,0 +1,428 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use actix_web::http::StatusCode;
+use actix_web::{HttpRequest, HttpResponse};
+use anyhow::{bail, Context};
+use clap::ValueEnum;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::body::Body;
+use crate::error::ApiError;
+use crate::Endpoint;
+
+/// The path of the JSON-RPC endpoint, served with `--jsonrpc`.
+pub const RPC: &str = "/rpc";
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+/// The implementation-defined server errors, e.g. the errors returned by the functions or the
+/// exceeded limits.
+const SERVER_ERROR: i64 = -32000;
+
+/// The names of the functions used as the JSON-RPC methods.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MethodNames {
+    /// The operation IDs of the functions, e.g. `add` or `math.add`
+    Name,
+    /// The paths of their endpoints, e.g. `/add` or `/math/add`
+    Path,
+}
+
+/// How the JSON-RPC methods resolve to the functions.
+#[derive(Clone, Debug)]
+pub enum Methods {
+    Names(MethodNames),
+    /// The methods mapped to the operation IDs or the paths of the functions, the others being
+    /// unknown.
+    Map(HashMap<String, String>),
+}
+
+impl Methods {
+    /// Load a JSON object mapping the methods to the functions, e.g. `{"math.sum": "/add"}`.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let file = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let map = serde_json::from_str(&file)
+            .with_context(|| format!("Failed to parse the method map of {}", path.display()))?;
+
+        Ok(Self::Map(map))
+    }
+
+    /// Check that the mapped functions are served.
+    pub fn check(&self, endpoints: &[Endpoint]) -> anyhow::Result<()> {
+        if let Self::Map(map) = self {
+            for (method, function) in map {
+                if !endpoints.iter().any(|endpoint| is(endpoint, function)) {
+                    bail!(
+                        "The `{}` method is mapped to `{}`, which is no served function",
+                        method,
+                        function
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn resolve<'a>(&self, endpoints: &'a [Endpoint], method: &str) -> Option<&'a Endpoint> {
+        let find = |f: &dyn Fn(&Endpoint) -> bool| endpoints.iter().find(|endpoint| f(endpoint));
+
+        match self {
+            Self::Names(MethodNames::Name) => find(&|endpoint| endpoint.operation_id() == method),
+            Self::Names(MethodNames::Path) => find(&|endpoint| endpoint.path == method),
+            Self::Map(map) => {
+                let function = map.get(method)?;
+
+                find(&|endpoint| is(endpoint, function))
+            }
+        }
+    }
+}
+
+/// Whether a function is named by its operation ID or its path.
+fn is(endpoint: &Endpoint, function: &str) -> bool {
+    endpoint.operation_id() == function || endpoint.path == function
+}
+
+/// A JSON-RPC error object.
+struct RpcError {
+    code: i64,
+    message: String,
+    data: Option<Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl ToString) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+            data: None,
+        }
+    }
+}
+
+impl From<ApiError> for RpcError {
+    fn from(error: ApiError) -> Self {
+        let code = match error.status {
+            StatusCode::BAD_REQUEST => INVALID_PARAMS,
+            status if status.is_server_error() => INTERNAL_ERROR,
+            _ => SERVER_ERROR,
+        };
+
+        Self {
+            code,
+            message: error.message,
+            data: (!error.details.is_empty()).then_some(Value::Object(error.details)),
+        }
+    }
+}
+
+/// Answer a JSON-RPC 2.0 request, or a batch of them, calling the functions their methods
+/// resolve to.
+///
+/// The notifications, i.e. the requests without an ID, are answered with a 204.
+pub async fn handle(
+    endpoints: &[Endpoint],
+    methods: &Methods,
+    request: &HttpRequest,
+    body: &[u8],
+    verbose_errors: bool,
+) -> HttpResponse {
+    let response = match serde_json::from_slice::<Value>(body) {
+        Err(e) => Some(response(
+            Value::Null,
+            Err(RpcError::new(PARSE_ERROR, format!("parse error: {}", e))),
+        )),
+        Ok(Value::Array(batch)) if batch.is_empty() => Some(response(
+            Value::Null,
+            Err(RpcError::new(INVALID_REQUEST, "empty batch")),
+        )),
+        Ok(Value::Array(batch)) => {
+            let mut responses = vec![];
+
+            for call in batch {
+                responses.extend(dispatch(endpoints, methods, request, call, verbose_errors).await);
+            }
+
+            (!responses.is_empty()).then_some(Value::Array(responses))
+        }
+        Ok(call) => dispatch(endpoints, methods, request, call, verbose_errors).await,
+    };
+
+    match response {
+        Some(response) => HttpResponse::Ok().json(response),
+        None => HttpResponse::NoContent().finish(),
+    }
+}
+
+/// Call the function of a single request, returning its response unless it is a notification.
+async fn dispatch(
+    endpoints: &[Endpoint],
+    methods: &Methods,
+    request: &HttpRequest,
+    call: Value,
+    verbose_errors: bool,
+) -> Option<Value> {
+    // Unlike a missing ID, a null ID still asks for a response
+    let id = call.get("id").cloned();
+    let result = match parse(endpoints, methods, call) {
+        Ok((endpoint, params)) => invoke(endpoint, request, params, verbose_errors).await,
+        Err(e) => Err(e),
+    };
+
+    match (id, &result) {
+        (Some(id), _) => Some(response(id, result)),
+        // An invalid request is answered even though its ID can't be told
+        (None, Err(e)) if e.code == INVALID_REQUEST => Some(response(Value::Null, result)),
+        (None, _) => None,
+    }
+}
+
+fn parse<'a>(
+    endpoints: &'a [Endpoint],
+    methods: &Methods,
+    call: Value,
+) -> Result<(&'a Endpoint, Option<Body>), RpcError> {
+    if call.get("jsonrpc").and_then(Value::as_str) != Some("2.0") {
+        return Err(RpcError::new(
+            INVALID_REQUEST,
+            "expected `\"jsonrpc\": \"2.0\"`",
+        ));
+    }
+
+    let method = call
+        .get("method")
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError::new(INVALID_REQUEST, "expected a string `method`"))?;
+    let endpoint = methods
+        .resolve(endpoints, method)
+        .ok_or_else(|| RpcError::new(METHOD_NOT_FOUND, format!("unknown method `{}`", method)))?;
+    let params = match call.get("params") {
+        None => None,
+        Some(params) => Some(Body::deserialize(params).map_err(|_| {
+            RpcError::new(
+                INVALID_PARAMS,
+                "expected a JSON object or array of parameters",
+            )
+        })?),
+    };
+
+    Ok((endpoint, params))
+}
+
+/// Call the function of an endpoint, turning the HTTP response of the call into a result.
+async fn invoke(
+    endpoint: &Endpoint,
+    request: &HttpRequest,
+    params: Option<Body>,
+    verbose_errors: bool,
+) -> Result<Value, RpcError> {
+    let response = endpoint.call(request, params, verbose_errors).await?;
+    let status = response.status();
+    let body = actix_web::body::to_bytes(response.into_body())
+        .await
+        .unwrap_or_default();
+    let value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+
+    match status.is_success() {
+        true => Ok(value),
+        // The error case of a returned result
+        false => Err(RpcError {
+            code: SERVER_ERROR,
+            message: String::from("the function returned an error"),
+            data: Some(value),
+        }),
+    }
+}
+
+fn response(id: Value, result: Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": id }),
+        Err(error) => {
+            let mut object = json!({ "code": error.code, "message": error.message });
+
+            if let Some(data) = error.data {
+                object["data"] = data;
+            }
+
+            json!({ "jsonrpc": "2.0", "error": object, "id": id })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+    use crate::{defaults, testing};
+
+    const WIT: &str = r#"
+        package test:rpc;
+
+        world rpc {
+            export add: func(a: u32, b: u32) -> u32;
+        }
+    "#;
+
+    /// The status and the JSON body of the answer to a JSON-RPC body.
+    async fn call(body: &str) -> (StatusCode, Value) {
+        call_with(&Methods::Names(MethodNames::Name), body).await
+    }
+
+    async fn call_with(methods: &Methods, body: &str) -> (StatusCode, Value) {
+        let endpoints = testing::endpoints(WIT, defaults());
+        let request = TestRequest::default().to_http_request();
+        let response = handle(&endpoints, methods, &request, body.as_bytes(), false).await;
+        let status = response.status();
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    #[actix_web::test]
+    async fn rejects_the_invalid_requests() {
+        let (status, body) = call(r#"{ "method": "add", "id": 1 }"#).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["error"]["code"], INVALID_REQUEST);
+        assert_eq!(body["id"], 1);
+
+        // Answered even without an ID
+        let (_, body) = call(r#"{ "jsonrpc": "2.0" }"#).await;
+        assert_eq!(body["error"]["code"], INVALID_REQUEST);
+        assert_eq!(body["error"]["message"], "expected a string `method`");
+        assert_eq!(body["id"], Value::Null);
+
+        let (_, body) = call("{").await;
+        assert_eq!(body["error"]["code"], PARSE_ERROR);
+    }
+
+    #[actix_web::test]
+    async fn rejects_the_unknown_methods() {
+        let (_, body) = call(r#"{ "jsonrpc": "2.0", "method": "sub", "id": "a" }"#).await;
+
+        assert_eq!(
+            body,
+            json!({
+                "jsonrpc": "2.0",
+                "error": { "code": METHOD_NOT_FOUND, "message": "unknown method `sub`" },
+                "id": "a",
+            })
+        );
+    }
+
+    #[actix_web::test]
+    async fn rejects_the_invalid_params() {
+        let (_, body) =
+            call(r#"{ "jsonrpc": "2.0", "method": "add", "params": 1, "id": 1 }"#).await;
+        assert_eq!(body["error"]["code"], INVALID_PARAMS);
+        assert_eq!(
+            body["error"]["message"],
+            "expected a JSON object or array of parameters"
+        );
+
+        let (_, body) =
+            call(r#"{ "jsonrpc": "2.0", "method": "add", "params": [1, "2"], "id": 1 }"#).await;
+        assert_eq!(body["error"]["code"], INVALID_PARAMS);
+
+        let (_, body) =
+            call(r#"{ "jsonrpc": "2.0", "method": "add", "params": { "a": 1 }, "id": null }"#)
+                .await;
+        assert_eq!(body["error"]["code"], INVALID_PARAMS);
+        assert_eq!(body["id"], Value::Null);
+    }
+
+    #[actix_web::test]
+    async fn answers_the_notifications_with_no_content() {
+        let (status, body) =
+            call(r#"{ "jsonrpc": "2.0", "method": "add", "params": [1, "2"] }"#).await;
+
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert_eq!(body, Value::Null);
+    }
+
+    #[actix_web::test]
+    async fn answers_the_batches() {
+        let (_, body) = call(
+            r#"[
+                { "jsonrpc": "2.0", "method": "sub", "id": 1 },
+                { "jsonrpc": "2.0", "method": "sub" },
+                { "method": "add", "id": 2 },
+                { "jsonrpc": "2.0", "method": "add", "params": [1], "id": 3 }
+            ]"#,
+        )
+        .await;
+        let codes = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|response| (response["id"].clone(), response["error"]["code"].clone()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            codes,
+            [
+                (json!(1), json!(METHOD_NOT_FOUND)),
+                (json!(2), json!(INVALID_REQUEST)),
+                (json!(3), json!(INVALID_PARAMS)),
+            ]
+        );
+
+        let (status, _) = call(r#"[{ "jsonrpc": "2.0", "method": "sub" }]"#).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+    }
+
+    #[actix_web::test]
+    async fn rejects_the_empty_batches() {
+        let (_, body) = call("[]").await;
+
+        assert_eq!(
+            body,
+            json!({
+                "jsonrpc": "2.0",
+                "error": { "code": INVALID_REQUEST, "message": "empty batch" },
+                "id": null,
+            })
+        );
+    }
+
+    #[actix_web::test]
+    async fn resolves_the_methods_with_a_map() {
+        let file = testing::TempFile::new("json", r#"{ "math.sum": "add", "plus": "/add" }"#);
+        let methods = Methods::load(file.path().as_ref()).unwrap();
+        let method = |method: &str| {
+            json!({ "jsonrpc": "2.0", "method": method, "params": [1, 2], "id": 1 }).to_string()
+        };
+
+        // The function traps, so the call reached it
+        for mapped in ["math.sum", "plus"] {
+            let (_, body) = call_with(&methods, &method(mapped)).await;
+            assert_eq!(body["error"]["code"], INTERNAL_ERROR, "{}", mapped);
+        }
+
+        let (_, body) = call_with(&methods, &method("add")).await;
+        assert_eq!(body["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(body["error"]["message"], "unknown method `add`");
+
+        let (_, body) = call_with(&Methods::Names(MethodNames::Path), &method("/add")).await;
+        assert_eq!(body["error"]["code"], INTERNAL_ERROR);
+
+        let endpoints = testing::endpoints(WIT, defaults());
+        assert!(methods.check(&endpoints).is_ok());
+        let error = Methods::Map(HashMap::from([("sub".into(), "sub".into())]))
+            .check(&endpoints)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The `sub` method is mapped to `sub`, which is no served function"
+        );
+    }
+}
@@ -0,0 +1,4839 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::{ErrorKind, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener};
+use std::ops::{Deref, RangeInclusive};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use actix_web::dev::Server;
+use actix_web::error::InternalError;
+use actix_web::http::header::{ContentType, HeaderName, HeaderValue};
+use actix_web::http::StatusCode;
+use actix_web::middleware::{Condition, DefaultHeaders};
+use actix_web::{web, App, FromRequest, HttpMessage, HttpRequest, HttpResponse, HttpServer};
+use anyhow::{anyhow, bail, Context};
+use clap::{Args, FromArgMatches, Parser, Subcommand, ValueEnum};
+use jsonschema::JSONSchema;
+use serde_json::Number;
+use utoipa::openapi::path::{
+    Operation, OperationBuilder, Parameter, ParameterBuilder, ParameterIn, PathItemBuilder,
+};
+use utoipa::openapi::request_body::{RequestBody, RequestBodyBuilder};
+use utoipa::openapi::schema::AnyOfBuilder;
+use utoipa::openapi::security::{Http, HttpAuthScheme, SecurityRequirement, SecurityScheme};
+use utoipa::openapi::tag::{Tag, TagBuilder};
+use utoipa::openapi::{
+    AllOfBuilder, ArrayBuilder, ComponentsBuilder, ContentBuilder, Discriminator, InfoBuilder,
+    ObjectBuilder, OneOfBuilder, OpenApi, OpenApiBuilder, PathItem, PathItemType, PathsBuilder,
+    Ref, RefOr, Required, Response, ResponseBuilder, Schema, SchemaType, ServerBuilder,
+};
+use utoipa::PartialSchema;
+use utoipa_swagger_ui::{Config as SwaggerConfig, SwaggerUi};
+use wasmtime::component::{Component, Func, Linker, List, Val};
+use wasmtime::{AsContext, AsContextMut, Config, Engine, Trap};
+use wit_component::DecodedWasm;
+use wit_parser::{
+    Docs, Function, Resolve, TypeDef, TypeDefKind, TypeId, TypeOwner, World, WorldItem, WorldKey,
+};
+
+use crate::auth::{BearerAuth, JwtValidator, BEARER_SCHEME};
+use crate::body::Body;
+use crate::cors::CorsOptions;
+use crate::error::ApiError;
+use crate::examples::Examples;
+use crate::fields::FieldSelection;
+use crate::jsonrpc::{MethodNames, Methods};
+use crate::limits::Limits;
+use crate::mapper::{BigIntAsString, IntegerFormats};
+use crate::metrics::{Metrics, METRICS};
+use crate::pool::{InstancePool, PooledInstance};
+use crate::proxy::{CallLog, LogFormat};
+use crate::source::{ComponentSource, FetchOptions};
+use crate::spec::{OpenApiVersion, SpecOptions};
+use crate::stream::ListBody;
+use crate::stubs::ImportValues;
+use crate::tls::TlsOptions;
+use crate::wasi::{Host, WasiOptions};
+use crate::webhooks::Webhook;
+
+pub use crate::mapper::SchemaMapper;
+
+mod auth;
+mod body;
+mod cache;
+mod cors;
+mod coverage;
+mod error;
+mod examples;
+mod fields;
+mod fuel;
+mod headers;
+mod health;
+mod info;
+mod jsonrpc;
+mod limits;
+mod mapper;
+mod metrics;
+mod pool;
+mod postman;
+mod proxy;
+mod repl;
+mod schemas;
+mod shutdown;
+mod source;
+mod spec;
+mod stream;
+mod stubs;
+#[cfg(test)]
+mod testing;
+mod timeout;
+mod tls;
+mod validation;
+mod wasi;
+mod webhooks;
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Cli {
+    /// Path or HTTP(S) URL of the WebAssembly module file, or `-` for the standard input, repeated
+    /// to serve several components under a prefix named after their file (e.g. `/add` for
+    /// `add.wasm`)
+    #[clap(short, long, required = true)]
+    file: Vec<ComponentSource>,
+
+    #[clap(flatten)]
+    fetch_options: FetchOptions,
+
+    /// Define the component's imports with stubs returning defaults, for testing in isolation
+    #[clap(long)]
+    stub_imports: bool,
+
+    /// YAML or JSON file mapping names of imported functions (e.g. `log` or `my:host/logging#log`)
+    /// to the fixed value they return, for the primitive results
+    #[clap(long)]
+    import_values: Option<PathBuf>,
+
+    #[clap(flatten)]
+    wasi_options: WasiOptions,
+
+    /// Compile the components on every start, instead of caching their compiled artifact next to
+    /// their file
+    #[clap(long)]
+    no_cache: bool,
+
+    /// Maximum fuel consumed by a single function call, which fails once exhausted
+    #[clap(long)]
+    max_fuel: Option<u64>,
+
+    /// Maximum time in milliseconds a single function call runs for, which fails once elapsed
+    #[clap(long)]
+    call_timeout: Option<u64>,
+
+    /// Maximum number of instances of each component, which bounds the number of concurrent
+    /// calls to its functions [default: the number of CPUs]
+    #[clap(long)]
+    max_instances: Option<usize>,
+
+    /// Maximum number of endpoints generated from the exported functions
+    #[clap(long)]
+    max_endpoints: Option<usize>,
+
+    /// What to do when the component exports more functions than --max-endpoints
+    #[clap(long, value_enum, default_value_t = MaxEndpointsPolicy::Error)]
+    max_endpoints_policy: MaxEndpointsPolicy,
+
+    #[clap(flatten)]
+    path_options: PathOptions,
+
+    #[clap(flatten)]
+    info_options: InfoOptions,
+
+    #[clap(flatten)]
+    value_options: ValueOptions,
+
+    #[clap(flatten)]
+    spec_options: SpecOptions,
+
+    #[clap(subcommand)]
+    command: Command,
+}
+
+// The command is parsed once, so the size of its largest variant doesn't matter
+#[allow(clippy::large_enum_variant)]
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Convert the WebAssembly module
+    Convert {
+        /// Specify the stream the OpenAPI definition is written to
+        #[clap(long, value_enum, default_value_t = OutputStream::Stdout)]
+        output_stream: OutputStream,
+
+        /// Specify the format the OpenAPI definition is written in, or export a Postman collection
+        /// instead
+        #[clap(long, value_enum, default_value_t = spec::Format::Json)]
+        format: spec::Format,
+
+        /// Indent the JSON definition, which is minified by default
+        #[clap(long)]
+        pretty: bool,
+
+        /// Declare a URL the API is served at (e.g. `https://api.example.com/v1`), repeated to
+        /// declare several servers
+        #[clap(long)]
+        server_url: Vec<String>,
+
+        /// Also write the schema of every named type to a standalone JSON Schema file in this
+        /// directory (e.g. `point.schema.json`), referencing the others by file name
+        #[clap(long)]
+        emit_schemas: Option<PathBuf>,
+    },
+
+    /// Report the functions whose types are not fully supported yet, as JSON
+    Coverage,
+
+    /// Check every endpoint can be served, failing when a function uses types that are not
+    /// supported yet
+    Validate,
+
+    /// Call the functions interactively, as `<function> <JSON object of named parameters>` lines
+    Repl,
+
+    /// Validate a JSON request body against the request schema of an endpoint, without calling it
+    ///
+    /// The request of a GET endpoint is the JSON object of its query parameters.
+    ValidateRequest {
+        /// Specify the path of the endpoint (e.g. `/add`)
+        path: String,
+
+        /// Read the request body from this file instead of the standard input
+        #[clap(long)]
+        body: Option<PathBuf>,
+    },
+
+    /// Serve the WebAssembly module
+    Serve {
+        /// Enable swagger documentation
+        #[clap(long, short)]
+        swagger: bool,
+
+        /// Enable the deep linking of tags and operations in Swagger UI (enabled by default)
+        #[clap(long, requires = "swagger", num_args = 0..=1, default_missing_value = "true")]
+        swagger_deep_linking: Option<bool>,
+
+        /// Enable the "Try it out" mode of the operations by default in Swagger UI
+        #[clap(long, requires = "swagger", num_args = 0..=1, default_missing_value = "true")]
+        swagger_default_try_it_out: Option<bool>,
+
+        /// Specify the default expansion of the operations and tags in Swagger UI
+        #[clap(long, value_enum, requires = "swagger")]
+        swagger_doc_expansion: Option<DocExpansion>,
+
+        /// Specify the server's bind address, an IPv4 or IPv6 one (e.g. `::1`, or `::` for every
+        /// interface, of both families where the system allows it)
+        #[clap(long, short, default_value = "127.0.0.1", value_parser = parse_address)]
+        address: IpAddr,
+
+        /// Bind every IPv4 interface, i.e. `0.0.0.0`, for the server to be reachable from other
+        /// hosts
+        #[clap(long, conflicts_with = "address")]
+        all_interfaces: bool,
+
+        /// Specify the server's bind port
+        #[clap(long, short, default_value_t = 8080)]
+        port: u16,
+
+        /// Require a bearer JWT validated against the JSON Web Key Set at this URL
+        #[clap(long)]
+        jwt_jwks_url: Option<String>,
+
+        /// Specify the audience the JWTs must be issued for
+        #[clap(long, requires = "jwt_jwks_url")]
+        jwt_audience: Option<String>,
+
+        /// Specify the issuer the JWTs must be issued by
+        #[clap(long, requires = "jwt_jwks_url")]
+        jwt_issuer: Option<String>,
+
+        /// Require this static bearer token, declared as the security scheme of the API
+        #[clap(long, conflicts_with = "jwt_jwks_url")]
+        auth_token: Option<String>,
+
+        /// Mount the endpoints, the spec and the Swagger UI under this path (e.g. `/api/v2`), for
+        /// the server to be deployed behind a reverse proxy forwarding it. The spec declares it in
+        /// its server URL, its paths staying relative to it as OpenAPI resolves them
+        #[clap(long, value_parser = parse_base_path)]
+        base_path: Option<String>,
+
+        /// Mount the endpoints and the spec under a version prefix (e.g. `v1`), derived from the
+        /// API version when no value is given, after the base path
+        #[clap(long, num_args = 0..=1, default_missing_value = "")]
+        path_version: Option<String>,
+
+        /// Only serve the endpoints of the functions annotated with `@public`
+        #[clap(long)]
+        public_only: bool,
+
+        /// Add a header to every response, as `<name>: <value>`
+        #[clap(long, value_parser = headers::parse_response_header)]
+        response_header: Vec<(HeaderName, HeaderValue)>,
+
+        /// Honor the `X-Forwarded-For` and `X-Forwarded-Proto` headers of the requests sent by
+        /// the proxy at this address, to log the address and scheme of the clients
+        #[clap(long)]
+        trusted_proxy: Vec<IpAddr>,
+
+        /// Specify the format of the access log of the requests
+        #[clap(long, value_enum, default_value_t = LogFormat::Plain)]
+        log_format: LogFormat,
+
+        /// Include the error messages of the failed function calls (e.g. the traps) in the 500
+        /// responses, which may leak details about the component
+        #[clap(long)]
+        verbose_errors: bool,
+
+        /// Serve a `/rpc` endpoint calling the functions with JSON-RPC 2.0 requests
+        #[clap(long)]
+        jsonrpc: bool,
+
+        /// Specify the names of the functions used as the JSON-RPC methods
+        #[clap(long, value_enum, default_value_t = MethodNames::Name, requires = "jsonrpc")]
+        rpc_methods: MethodNames,
+
+        /// Resolve the JSON-RPC methods with a JSON object mapping them to the operation IDs or
+        /// the paths of the functions, the unmapped methods being unknown
+        #[clap(long, requires = "jsonrpc", conflicts_with = "rpc_methods")]
+        rpc_method_map: Option<PathBuf>,
+
+        /// Maximum size in bytes of the request bodies, above which they are rejected with a 413
+        #[clap(long, default_value_t = 256 * 1024)]
+        max_body_size: usize,
+
+        /// Serve the Prometheus metrics of the function calls at `/metrics`: their number, failures
+        /// and durations, by function
+        #[clap(long)]
+        metrics: bool,
+
+        /// Don't serve the `/healthz` and `/readyz` probes, e.g. when the component exports a
+        /// function served at one of these paths
+        #[clap(long)]
+        no_health: bool,
+
+        /// Seconds given to the in-flight requests to complete once the server is asked to stop
+        /// with SIGINT or SIGTERM, after which they are cut off
+        #[clap(long, default_value_t = 30)]
+        shutdown_timeout: u64,
+
+        #[clap(flatten)]
+        cors: CorsOptions,
+
+        #[clap(flatten)]
+        tls: TlsOptions,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum MaxEndpointsPolicy {
+    /// Fail to start
+    Error,
+    /// Keep the first endpoints, in export order, and warn about the others
+    Truncate,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum DocExpansion {
+    /// Expand the tags only
+    List,
+    /// Expand the tags and operations
+    Full,
+    /// Expand nothing
+    None,
+}
+
+impl DocExpansion {
+    fn as_str(self) -> &'static str {
+        match self {
+            DocExpansion::List => "list",
+            DocExpansion::Full => "full",
+            DocExpansion::None => "none",
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// The property naming the case of a variant value.
+const VARIANT_TAG: &str = "tag";
+/// The property holding the payload of a variant value, for the cases having one.
+const VARIANT_VALUE: &str = "value";
+/// The properties holding the `ok` and `err` cases of the results nested in other values.
+const RESULT_OK: &str = "ok";
+const RESULT_ERR: &str = "err";
+
+/// The name wit-component gives to the world of every decoded component.
+const DECODED_WORLD: &str = "root";
+
+const PATH_WORLD: &str = "{world}";
+const PATH_INTERFACE: &str = "{interface}";
+const PATH_FUNC: &str = "{func}";
+
+/// A WIT type, converted into the JSON schema of its values.
+#[derive(Clone, Copy)]
+pub struct Type(pub wit_parser::Type);
+
+impl Type {
+    pub fn into_schema(self, resolve: &Resolve, mapper: &dyn SchemaMapper) -> RefOr<Schema> {
+        let wit_parser::Type::Id(id) = self.0 else {
+            return mapper
+                .map(&self, resolve)
+                .unwrap_or_else(|| self.primitive_schema());
+        };
+
+        match schema_name(resolve, id) {
+            Some(name) => RefOr::Ref(Ref::from_schema_name(name)),
+            None => Self::definition(resolve, id, mapper),
+        }
+    }
+
+    fn primitive_schema(self) -> RefOr<Schema> {
+        match self.0 {
+            wit_parser::Type::Bool => bool::schema(),
+            wit_parser::Type::U8 => u8::schema(),
+            wit_parser::Type::U16 => u16::schema(),
+            wit_parser::Type::U32 => u32::schema(),
+            wit_parser::Type::U64 => u64::schema(),
+            wit_parser::Type::S8 => i8::schema(),
+            wit_parser::Type::S16 => i16::schema(),
+            wit_parser::Type::S32 => i32::schema(),
+            wit_parser::Type::S64 => i64::schema(),
+            wit_parser::Type::Float32 => f32::schema(),
+            wit_parser::Type::Float64 => f64::schema(),
+            wit_parser::Type::Char => char::schema(),
+            wit_parser::Type::String => String::schema(),
+            wit_parser::Type::Id(_) => unreachable!("not a primitive type"),
+        }
+    }
+
+    /// The schema of a type definition, referencing the named types it's made of.
+    fn definition(resolve: &Resolve, id: TypeId, mapper: &dyn SchemaMapper) -> RefOr<Schema> {
+        if let Some(schema) = mapper.map(&Type(wit_parser::Type::Id(id)), resolve) {
+            return schema;
+        }
+
+        let def = &resolve.types[id];
+
+        match &def.kind {
+            TypeDefKind::Record(record) => RefOr::T(Schema::Object(
+                record
+                    .fields
+                    .iter()
+                    .fold(ObjectBuilder::new(), |obj, field| {
+                        let schema = Type(field.ty).into_schema(resolve, mapper);
+                        let schema = match &field.docs.contents {
+                            Some(docs) => with_description(schema, docs.trim()),
+                            None => schema,
+                        };
+
+                        obj.property(&field.name, schema).required(&field.name)
+                    })
+                    .description(def.docs.contents.as_deref().map(str::trim))
+                    .build(),
+            )),
+            TypeDefKind::List(ty) => RefOr::T(Schema::Array(
+                ArrayBuilder::new()
+                    .items(Type(*ty).into_schema(resolve, mapper))
+                    .build(),
+            )),
+            TypeDefKind::Enum(enum_) => RefOr::T(Schema::Object(
+                ObjectBuilder::new()
+                    .schema_type(SchemaType::String)
+                    .enum_values(Some(enum_.cases.iter().map(|case| case.name.as_str())))
+                    .description(cases_description(
+                        &def.docs,
+                        enum_.cases.iter().map(|case| (&case.name, &case.docs)),
+                    ))
+                    .build(),
+            )),
+            TypeDefKind::Flags(flags) => RefOr::T(Schema::Array(
+                ArrayBuilder::new()
+                    .items(
+                        ObjectBuilder::new()
+                            .schema_type(SchemaType::String)
+                            .enum_values(Some(flags.flags.iter().map(|flag| flag.name.as_str()))),
+                    )
+                    .unique_items(true)
+                    .description(cases_description(
+                        &def.docs,
+                        flags.flags.iter().map(|flag| (&flag.name, &flag.docs)),
+                    ))
+                    .build(),
+            )),
+            TypeDefKind::Option(ty) => nullable(Type(*ty).into_schema(resolve, mapper)),
+            // OpenAPI 3.0 has no `prefixItems`, so the elements can only be constrained
+            // to any of the tuple types, and the arity to the tuple's
+            TypeDefKind::Tuple(tuple) => RefOr::T(Schema::Array(
+                ArrayBuilder::new()
+                    .items(Schema::AnyOf(
+                        tuple
+                            .types
+                            .iter()
+                            .fold(AnyOfBuilder::new(), |any_of, ty| {
+                                any_of.item(Type(*ty).into_schema(resolve, mapper))
+                            })
+                            .build(),
+                    ))
+                    .min_items(Some(tuple.types.len()))
+                    .max_items(Some(tuple.types.len()))
+                    .description(def.docs.contents.as_deref().map(str::trim))
+                    .build(),
+            )),
+            TypeDefKind::Variant(variant) => RefOr::T(Schema::OneOf(
+                variant
+                    .cases
+                    .iter()
+                    .fold(OneOfBuilder::new(), |one_of, case| {
+                        let tag = ObjectBuilder::new()
+                            .schema_type(SchemaType::String)
+                            .enum_values(Some([case.name.as_str()]));
+                        let object = ObjectBuilder::new()
+                            .property(VARIANT_TAG, tag)
+                            .required(VARIANT_TAG)
+                            .description(case.docs.contents.as_deref().map(str::trim));
+
+                        one_of.item(match case.ty {
+                            Some(ty) => object
+                                .property(VARIANT_VALUE, Type(ty).into_schema(resolve, mapper))
+                                .required(VARIANT_VALUE),
+                            None => object,
+                        })
+                    })
+                    .discriminator(Some(Discriminator::new(VARIANT_TAG)))
+                    .description(def.docs.contents.as_deref().map(str::trim))
+                    .build(),
+            )),
+            TypeDefKind::Result(result) => RefOr::T(Schema::OneOf(
+                [(RESULT_OK, result.ok), (RESULT_ERR, result.err)]
+                    .into_iter()
+                    .fold(OneOfBuilder::new(), |one_of, (case, ty)| {
+                        let schema = match ty {
+                            Some(ty) => Type(ty).into_schema(resolve, mapper),
+                            None => RefOr::T(Schema::Object(ObjectBuilder::new().build())),
+                        };
+
+                        one_of.item(ObjectBuilder::new().property(case, schema).required(case))
+                    })
+                    .description(def.docs.contents.as_deref().map(str::trim))
+                    .build(),
+            )),
+            TypeDefKind::Type(ty) => Type(*ty).into_schema(resolve, mapper),
+            _ => String::schema(),
+        }
+    }
+
+    /// Collect the named type definitions this type is made of, by `components/schemas` name.
+    fn collect_named(self, resolve: &Resolve, named: &mut BTreeMap<String, TypeId>) {
+        let wit_parser::Type::Id(id) = self.0 else {
+            return;
+        };
+
+        if let Some(name) = schema_name(resolve, id) {
+            if named.insert(name, id).is_some() {
+                return;
+            }
+        }
+
+        let types: Vec<wit_parser::Type> = match &resolve.types[id].kind {
+            TypeDefKind::Record(record) => record.fields.iter().map(|field| field.ty).collect(),
+            TypeDefKind::Tuple(tuple) => tuple.types.clone(),
+            TypeDefKind::Variant(variant) => {
+                variant.cases.iter().filter_map(|case| case.ty).collect()
+            }
+            TypeDefKind::Result(result) => result.ok.into_iter().chain(result.err).collect(),
+            TypeDefKind::List(ty) | TypeDefKind::Option(ty) | TypeDefKind::Type(ty) => vec![*ty],
+            _ => vec![],
+        };
+
+        for ty in types {
+            Type(ty).collect_named(resolve, named);
+        }
+    }
+
+    /// Whether the type is mapped to a schema and converted from/to JSON without any fallback.
+    pub fn is_supported(self, resolve: &Resolve) -> bool {
+        self.unsupported(resolve).is_none()
+    }
+
+    /// The kind of the first type that isn't supported yet, either this one or a nested one.
+    pub fn unsupported(self, resolve: &Resolve) -> Option<&'static str> {
+        fn first(
+            types: impl IntoIterator<Item = wit_parser::Type>,
+            resolve: &Resolve,
+        ) -> Option<&'static str> {
+            types
+                .into_iter()
+                .find_map(|ty| Type(ty).unsupported(resolve))
+        }
+
+        match self.0 {
+            wit_parser::Type::Id(id) => match &resolve.types[id].kind {
+                TypeDefKind::Record(record) => first(record.fields.iter().map(|f| f.ty), resolve),
+                TypeDefKind::Enum(_) | TypeDefKind::Flags(_) => None,
+                TypeDefKind::Tuple(tuple) => first(tuple.types.iter().copied(), resolve),
+                TypeDefKind::Variant(variant) => {
+                    first(variant.cases.iter().filter_map(|case| case.ty), resolve)
+                }
+                TypeDefKind::Result(result) => {
+                    first(result.ok.into_iter().chain(result.err), resolve)
+                }
+                TypeDefKind::List(ty) | TypeDefKind::Option(ty) | TypeDefKind::Type(ty) => {
+                    Type(*ty).unsupported(resolve)
+                }
+                _ => Some(self.kind(resolve)),
+            },
+            _ => None,
+        }
+    }
+
+    pub fn kind(self, resolve: &Resolve) -> &'static str {
+        match self.0 {
+            wit_parser::Type::Bool => "bool",
+            wit_parser::Type::U8 => "u8",
+            wit_parser::Type::U16 => "u16",
+            wit_parser::Type::U32 => "u32",
+            wit_parser::Type::U64 => "u64",
+            wit_parser::Type::S8 => "s8",
+            wit_parser::Type::S16 => "s16",
+            wit_parser::Type::S32 => "s32",
+            wit_parser::Type::S64 => "s64",
+            wit_parser::Type::Float32 => "float32",
+            wit_parser::Type::Float64 => "float64",
+            wit_parser::Type::Char => "char",
+            wit_parser::Type::String => "string",
+            wit_parser::Type::Id(id) => match resolve.types[id].kind {
+                TypeDefKind::Record(_) => "record",
+                TypeDefKind::Resource => "resource",
+                TypeDefKind::Handle(_) => "handle",
+                TypeDefKind::Flags(_) => "flags",
+                TypeDefKind::Tuple(_) => "tuple",
+                TypeDefKind::Variant(_) => "variant",
+                TypeDefKind::Enum(_) => "enum",
+                TypeDefKind::Option(_) => "option",
+                TypeDefKind::Result(_) => "result",
+                TypeDefKind::List(_) => "list",
+                TypeDefKind::Future(_) => "future",
+                TypeDefKind::Stream(_) => "stream",
+                TypeDefKind::Type(ty) => Type(ty).kind(resolve),
+                TypeDefKind::Unknown => "unknown",
+            },
+        }
+    }
+}
+
+/// An HTTP endpoint calling a function exported by a component.
+#[derive(Clone)]
+pub struct Endpoint {
+    pub path: String,
+    /// The name of the component exporting the function, when serving several ones.
+    pub component: Option<String>,
+    pub interface: Option<String>,
+    pub prototype: wit_parser::Function,
+    /// The name of the component instance exporting the function, if not the component itself.
+    pub instance: Option<String>,
+    /// The tag grouping the endpoint with the others of its interface, or of its component for
+    /// the root functions.
+    pub tag: Tag,
+    pub(crate) limits: Arc<Limits>,
+    pub(crate) webhooks: Vec<Webhook>,
+    /// Whether the function is annotated with `@public`, rather than `@internal` by default.
+    pub public: bool,
+    /// The HTTP method of the endpoint: GET for the functions without parameters, POST otherwise,
+    /// unless overridden with `@method get` or `@method post`.
+    pub method: PathItemType,
+    /// The example of the request body, given with `@example-request {json}`.
+    pub request_example: Option<serde_json::Value>,
+    /// The example of the success response, given with `@example-response {json}`.
+    pub response_example: Option<serde_json::Value>,
+    /// The HTTP status of the success responses, given with `@status 201`, instead of 200, or 204
+    /// for the responses without a body.
+    pub status: Option<StatusCode>,
+    pub max_fuel: Option<u64>,
+    pub options: Arc<ValueOptions>,
+    /// The WIT types the schemas of the parameters and results are built from.
+    pub resolve: Arc<Resolve>,
+    /// The custom mapping of the WIT types to schemas.
+    pub(crate) mapper: Arc<dyn SchemaMapper>,
+    /// The validator of the named parameters against their schemas, with `--strict-validation`.
+    pub(crate) validator: Option<Arc<JSONSchema>>,
+    /// The instances of the component exporting the function.
+    pub(crate) pool: Arc<InstancePool>,
+}
+
+impl Endpoint {
+    pub(crate) fn new(
+        exported: &ExportedFunction,
+        path_options: &PathOptions,
+        max_fuel: Option<u64>,
+        options: Arc<ValueOptions>,
+        resolve: Arc<Resolve>,
+        mapper: Arc<dyn SchemaMapper>,
+        pool: Arc<InstancePool>,
+    ) -> anyhow::Result<Self> {
+        let prototype = exported.function.clone();
+        let limits = Limits::from_annotations(annotations(&prototype))
+            .with_context(|| format!("Invalid limits for function `{}`", prototype.name))?;
+        let webhooks = Webhook::from_annotations(annotations(&prototype))
+            .with_context(|| format!("Invalid webhooks for function `{}`", prototype.name))?;
+        let public = annotations(&prototype).any(|(tag, _)| tag == "public");
+        let method = match annotations(&prototype).find(|(tag, _)| *tag == "method") {
+            None if prototype.params.is_empty() => PathItemType::Get,
+            None => PathItemType::Post,
+            Some((_, method)) if method.eq_ignore_ascii_case("get") => PathItemType::Get,
+            Some((_, method)) if method.eq_ignore_ascii_case("post") => PathItemType::Post,
+            Some((_, method)) => bail!(
+                "Invalid method `{}` for function `{}`, expected `get` or `post`",
+                method,
+                prototype.name
+            ),
+        };
+        let example = |tag: &str| -> anyhow::Result<Option<serde_json::Value>> {
+            annotations(&prototype)
+                .find(|(name, _)| *name == tag)
+                .map(|(_, example)| serde_json::from_str(example))
+                .transpose()
+                .with_context(|| format!("Invalid @{} for function `{}`", tag, prototype.name))
+        };
+        let request_example = example("example-request")?;
+        let response_example = example("example-response")?;
+        let status = annotations(&prototype)
+            .find(|(tag, _)| *tag == "status")
+            .map(|(_, status)| {
+                status
+                    .parse()
+                    .ok()
+                    .and_then(|status| StatusCode::from_u16(status).ok())
+                    .filter(|status| status.is_success())
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "Invalid @status `{}` for function `{}`, expected a 2xx status code",
+                            status,
+                            prototype.name
+                        )
+                    })
+            })
+            .transpose()?;
+        if public && annotations(&prototype).any(|(tag, _)| tag == "internal") {
+            bail!(
+                "Function `{}` can't be both @public and @internal",
+                prototype.name
+            );
+        }
+
+        let mut endpoint = Self {
+            path: exported.path(path_options),
+            component: exported.component.clone(),
+            interface: exported.interface.clone(),
+            prototype,
+            instance: exported.instance.clone(),
+            tag: exported.tag(),
+            limits: Arc::new(limits),
+            webhooks,
+            public,
+            method,
+            request_example,
+            response_example,
+            status,
+            max_fuel,
+            options,
+            resolve,
+            mapper,
+            validator: None,
+            pool,
+        };
+        if endpoint.status == Some(StatusCode::NO_CONTENT) && endpoint.returns_body() {
+            bail!(
+                "Invalid @status `204` for function `{}`, which returns a value",
+                endpoint.prototype.name
+            );
+        }
+        if endpoint.options.strict_validation {
+            endpoint.validator = Some(Arc::new(endpoint.parameters_validator().with_context(
+                || format!("Invalid schema for function `{}`", endpoint.prototype.name),
+            )?));
+        }
+
+        for name in endpoint.param_descriptions().keys() {
+            if !endpoint
+                .prototype
+                .params
+                .iter()
+                .any(|(param, _)| param == name)
+            {
+                log::warn!(
+                    "Function `{}` documents an unknown parameter `{}`",
+                    endpoint.prototype.name,
+                    name
+                );
+            }
+        }
+
+        Ok(endpoint)
+    }
+}
+
+/// The `components/schemas` name of a named type definition, qualified by its interface when
+/// another type has the same name, or `None` when the type is always inlined.
+fn schema_name(resolve: &Resolve, id: TypeId) -> Option<String> {
+    fn is_component(def: &TypeDef) -> bool {
+        def.name.is_some()
+            && matches!(
+                def.kind,
+                TypeDefKind::Record(_)
+                    | TypeDefKind::Variant(_)
+                    | TypeDefKind::Enum(_)
+                    | TypeDefKind::Flags(_)
+                    | TypeDefKind::Tuple(_)
+                    | TypeDefKind::List(_)
+                    | TypeDefKind::Option(_)
+                    | TypeDefKind::Result(_)
+            )
+    }
+
+    let def = &resolve.types[id];
+    if !is_component(def) {
+        return None;
+    }
+
+    let name = def.name.clone()?;
+    let ambiguous = resolve
+        .types
+        .iter()
+        .any(|(other, d)| other != id && is_component(d) && d.name.as_ref() == Some(&name));
+
+    match (ambiguous, def.owner) {
+        (true, TypeOwner::Interface(interface)) => match &resolve.interfaces[interface].name {
+            Some(interface) => Some(format!("{}.{}", interface, name)),
+            None => Some(name),
+        },
+        _ => Some(name),
+    }
+}
+
+/// Mark a schema as accepting `null`, for the `none` case of the options.
+fn nullable(schema: RefOr<Schema>) -> RefOr<Schema> {
+    match schema {
+        RefOr::T(Schema::Array(mut schema)) => {
+            schema.nullable = true;
+            RefOr::T(Schema::Array(schema))
+        }
+        RefOr::T(Schema::Object(mut schema)) => {
+            schema.nullable = true;
+            RefOr::T(Schema::Object(schema))
+        }
+        RefOr::T(Schema::OneOf(mut schema)) => {
+            schema.nullable = true;
+            RefOr::T(Schema::OneOf(schema))
+        }
+        RefOr::T(Schema::AllOf(mut schema)) => {
+            schema.nullable = true;
+            RefOr::T(Schema::AllOf(schema))
+        }
+        RefOr::T(Schema::AnyOf(mut schema)) => {
+            schema.nullable = true;
+            RefOr::T(Schema::AnyOf(schema))
+        }
+        // References can't have siblings in OpenAPI 3.0
+        schema @ RefOr::Ref(_) => RefOr::T(Schema::AllOf(
+            AllOfBuilder::new().item(schema).nullable(true).build(),
+        )),
+        schema => schema,
+    }
+}
+
+/// The description of an enum or flags, followed by a list of the documented cases, since OpenAPI
+/// can't describe the values of an `enum` separately.
+fn cases_description<'a>(
+    docs: &Docs,
+    cases: impl Iterator<Item = (&'a String, &'a Docs)>,
+) -> Option<String> {
+    let cases = cases
+        .filter_map(|(name, docs)| {
+            let docs = docs.contents.as_deref()?.trim();
+
+            Some(format!("- `{}`: {}", name, docs.replace('\n', " ")))
+        })
+        .collect::<Vec<_>>();
+    let description = docs.contents.as_deref().map(str::trim);
+
+    match (description, cases.is_empty()) {
+        (description, true) => description.map(str::to_string),
+        (None, false) => Some(cases.join("\n")),
+        (Some(description), false) => Some(format!("{}\n\n{}", description, cases.join("\n"))),
+    }
+}
+
+fn with_description(schema: RefOr<Schema>, description: &str) -> RefOr<Schema> {
+    let description = Some(description.to_string());
+
+    match schema {
+        RefOr::T(Schema::Array(mut schema)) => {
+            schema.description = description;
+            RefOr::T(Schema::Array(schema))
+        }
+        RefOr::T(Schema::Object(mut schema)) => {
+            schema.description = description;
+            RefOr::T(Schema::Object(schema))
+        }
+        RefOr::T(Schema::OneOf(mut schema)) => {
+            schema.description = description;
+            RefOr::T(Schema::OneOf(schema))
+        }
+        RefOr::T(Schema::AllOf(mut schema)) => {
+            schema.description = description;
+            RefOr::T(Schema::AllOf(schema))
+        }
+        RefOr::T(Schema::AnyOf(mut schema)) => {
+            schema.description = description;
+            RefOr::T(Schema::AnyOf(schema))
+        }
+        // References can't have siblings in OpenAPI 3.0
+        schema @ RefOr::Ref(_) => RefOr::T(Schema::AllOf(
+            AllOfBuilder::new()
+                .item(schema)
+                .description(description)
+                .build(),
+        )),
+        schema => schema,
+    }
+}
+
+/// Parse the `@tag value` annotation lines of a function's docs.
+fn annotations(prototype: &Function) -> impl Iterator<Item = (&str, &str)> {
+    prototype
+        .docs
+        .contents
+        .iter()
+        .flat_map(|docs| docs.lines())
+        .filter_map(|line| {
+            let annotation = line.trim().strip_prefix('@')?;
+
+            Some(match annotation.split_once(char::is_whitespace) {
+                Some((tag, value)) => (tag, value.trim()),
+                None => (annotation, ""),
+            })
+        })
+}
+
+/// Options of the conversions between JSON and component values.
+#[derive(Args, Debug, Clone, Default)]
+pub struct ValueOptions {
+    /// Accept numbers given as JSON strings (e.g. "3.14") for float parameters
+    #[clap(long)]
+    coerce_strings: bool,
+
+    /// Reject float32 parameters whose value is out of range or can't be represented exactly
+    #[clap(long)]
+    strict_floats: bool,
+
+    /// Reject request bodies with duplicated keys, instead of keeping the last value
+    #[clap(long)]
+    reject_duplicate_keys: bool,
+
+    /// Validate the parameters against their schemas before decoding them, rejecting the unknown
+    /// fields of the records and any value the schemas don't describe
+    #[clap(long)]
+    strict_validation: bool,
+
+    /// How NaN and infinite float results are serialized, since JSON numbers can't represent them
+    #[clap(long, value_enum, default_value_t = NonFinitePolicy::Null)]
+    non_finite: NonFinitePolicy,
+
+    /// Represent the u64 and s64 values as JSON strings (e.g. "18446744073709551615"), which
+    /// JavaScript numbers can't all hold exactly, still accepting numbers for the parameters
+    #[clap(long)]
+    bigint_as_string: bool,
+
+    /// How enum values are represented, by case name or by index
+    #[clap(long, value_enum, default_value_t = EnumAs::String)]
+    enum_as: EnumAs,
+
+    /// Let the callers select the record fields of the results with a `fields` query parameter
+    /// (e.g. `?fields=id,name`), ignoring the unknown field names unless `reject` is given
+    #[clap(long, value_enum, num_args = 0..=1, default_missing_value = "ignore")]
+    field_selection: Option<FieldSelection>,
+
+    /// The HTTP status of the responses of the functions returning the `err` case of a result
+    #[clap(long, default_value_t = 400, value_parser = clap::value_parser!(u16).range(400..600))]
+    result_error_status: u16,
+}
+
+impl ValueOptions {
+    /// The schemas of the values, which differ from the built-in ones for `--bigint-as-string`,
+    /// and for the integers of an OpenAPI 3.1 document.
+    fn mapper(&self, version: OpenApiVersion) -> Arc<dyn SchemaMapper> {
+        match (self.bigint_as_string, version) {
+            (true, OpenApiVersion::V3_1) => Arc::new(|ty: &Type, resolve: &Resolve| {
+                BigIntAsString
+                    .map(ty, resolve)
+                    .or_else(|| IntegerFormats.map(ty, resolve))
+            }),
+            (true, OpenApiVersion::V3_0) => Arc::new(BigIntAsString),
+            (false, OpenApiVersion::V3_1) => Arc::new(IntegerFormats),
+            (false, OpenApiVersion::V3_0) => Arc::new(()),
+        }
+    }
+
+    fn result_error_status(&self) -> StatusCode {
+        StatusCode::from_u16(self.result_error_status).unwrap_or(StatusCode::BAD_REQUEST)
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+enum EnumAs {
+    /// The index of the case
+    Int,
+    /// The name of the case
+    #[default]
+    String,
+    /// Accept both the name and the index, and return the name
+    Both,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+enum NonFinitePolicy {
+    /// Serialize as `null`
+    #[default]
+    Null,
+    /// Serialize as the "NaN", "Infinity" and "-Infinity" strings
+    String,
+}
+
+#[derive(Debug)]
+pub struct DecodeError(String);
+
+impl DecodeError {
+    /// An error for a JSON value of the wrong type, e.g. "expected an integer, got a string".
+    fn expected(expected: &str, v: &serde_json::Value) -> Self {
+        let got = match v {
+            serde_json::Value::Null => "null",
+            serde_json::Value::Bool(_) => "a boolean",
+            serde_json::Value::Number(_) => "a number",
+            serde_json::Value::String(_) => "a string",
+            serde_json::Value::Array(_) => "an array",
+            serde_json::Value::Object(_) => "an object",
+        };
+
+        Self(format!("expected {}, got {}", expected, got))
+    }
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A component value, converted from and into JSON.
+pub struct Value(pub Val);
+
+impl Deref for Value {
+    type Target = Val;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Value {
+    /// Convert a JSON value, moving its strings and arrays into the component value rather than
+    /// copying them, so that large request bodies aren't held twice in memory.
+    pub fn from_json(
+        v: serde_json::Value,
+        ty: &wasmtime::component::Type,
+        options: &ValueOptions,
+    ) -> Result<Self, DecodeError> {
+        use wasmtime::component::Type;
+
+        Ok(Self(match ty {
+            Type::Bool => Val::Bool(
+                v.as_bool()
+                    .ok_or_else(|| DecodeError::expected("a boolean", &v))?,
+            ),
+            Type::U8 => Val::U8(Self::integer_from_json(&v, "u8", u8::MIN..=u8::MAX)?),
+            Type::U16 => Val::U16(Self::integer_from_json(&v, "u16", u16::MIN..=u16::MAX)?),
+            Type::U32 => Val::U32(Self::integer_from_json(&v, "u32", u32::MIN..=u32::MAX)?),
+            Type::U64 => Val::U64(Self::bigint_from_json(
+                &v,
+                "u64",
+                u64::MIN..=u64::MAX,
+                options,
+            )?),
+            Type::S8 => Val::S8(Self::integer_from_json(&v, "s8", i8::MIN..=i8::MAX)?),
+            Type::S16 => Val::S16(Self::integer_from_json(&v, "s16", i16::MIN..=i16::MAX)?),
+            Type::S32 => Val::S32(Self::integer_from_json(&v, "s32", i32::MIN..=i32::MAX)?),
+            Type::S64 => Val::S64(Self::bigint_from_json(
+                &v,
+                "s64",
+                i64::MIN..=i64::MAX,
+                options,
+            )?),
+            Type::Float32 => Val::Float32(Self::float32_from_json(&v, options)?),
+            Type::Float64 => Val::Float64(Self::float_from_json(&v, options)?),
+            Type::String => match v {
+                serde_json::Value::String(s) => Val::String(s.into()),
+                v => return Err(DecodeError::expected("a string", &v)),
+            },
+            Type::Char => Val::Char(Self::char_from_json(&v)?),
+            Type::List(ty) => Self::list_from_json(v, ty, options)?,
+            Type::Record(ty) => Self::record_from_json(v, ty, options)?,
+            Type::Enum(ty) => Self::enum_from_json(&v, ty, options)?,
+            Type::Tuple(ty) => Self::tuple_from_json(v, ty, options)?,
+            Type::Variant(ty) => Self::variant_from_json(v, ty, options)?,
+            Type::Flags(ty) => Self::flags_from_json(v, ty)?,
+            Type::Result(ty) => Self::result_from_json(v, ty, options)?,
+            Type::Option(ty) => {
+                let value = match v {
+                    serde_json::Value::Null => None,
+                    v => Some(Self::from_json(v, &ty.ty(), options)?.0),
+                };
+
+                ty.new_val(value)
+                    .map_err(|e| DecodeError(format!("{:#}", e)))?
+            }
+            _ => {
+                return Err(DecodeError(String::from(
+                    "resources can't be given as JSON values",
+                )))
+            }
+        }))
+    }
+
+    fn list_from_json(
+        v: serde_json::Value,
+        ty: &wasmtime::component::types::List,
+        options: &ValueOptions,
+    ) -> Result<Val, DecodeError> {
+        let serde_json::Value::Array(items) = v else {
+            return Err(DecodeError::expected("an array", &v));
+        };
+        let values = match ty.ty() {
+            // Byte lists can be large, so they skip the generic conversion of each element
+            wasmtime::component::Type::U8 => items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| {
+                    item.as_u64()
+                        .and_then(|byte| u8::try_from(byte).ok())
+                        .map(Val::U8)
+                        .ok_or_else(|| DecodeError(format!("element {}: expected a byte", i)))
+                })
+                .collect::<Result<Box<[Val]>, _>>()?,
+            element => items
+                .into_iter()
+                .enumerate()
+                .map(|(i, item)| {
+                    Self::from_json(item, &element, options)
+                        .map(|v| v.0)
+                        .map_err(|e| DecodeError(format!("element {}: {}", i, e)))
+                })
+                .collect::<Result<Box<[Val]>, _>>()?,
+        };
+
+        ty.new_val(values)
+            .map_err(|e| DecodeError(format!("{:#}", e)))
+    }
+
+    fn record_from_json(
+        v: serde_json::Value,
+        ty: &wasmtime::component::types::Record,
+        options: &ValueOptions,
+    ) -> Result<Val, DecodeError> {
+        let serde_json::Value::Object(mut fields) = v else {
+            return Err(DecodeError::expected("an object", &v));
+        };
+        let values = ty
+            .fields()
+            .map(|field| {
+                let v = fields
+                    .remove(field.name)
+                    .ok_or_else(|| DecodeError(format!("missing field `{}`", field.name)))?;
+
+                Self::from_json(v, &field.ty, options)
+                    .map(|v| (field.name, v.0))
+                    .map_err(|e| DecodeError(format!("field `{}`: {}", field.name, e)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        ty.new_val(values)
+            .map_err(|e| DecodeError(format!("{:#}", e)))
+    }
+
+    fn tuple_from_json(
+        v: serde_json::Value,
+        ty: &wasmtime::component::types::Tuple,
+        options: &ValueOptions,
+    ) -> Result<Val, DecodeError> {
+        let serde_json::Value::Array(items) = v else {
+            return Err(DecodeError::expected("an array", &v));
+        };
+        if items.len() != ty.types().len() {
+            return Err(DecodeError(format!(
+                "expected {} elements, got {}",
+                ty.types().len(),
+                items.len()
+            )));
+        }
+
+        let values = items
+            .into_iter()
+            .zip(ty.types())
+            .enumerate()
+            .map(|(i, (item, element))| {
+                Self::from_json(item, &element, options)
+                    .map(|v| v.0)
+                    .map_err(|e| DecodeError(format!("element {}: {}", i, e)))
+            })
+            .collect::<Result<Box<[Val]>, _>>()?;
+
+        ty.new_val(values)
+            .map_err(|e| DecodeError(format!("{:#}", e)))
+    }
+
+    fn variant_from_json(
+        v: serde_json::Value,
+        ty: &wasmtime::component::types::Variant,
+        options: &ValueOptions,
+    ) -> Result<Val, DecodeError> {
+        let serde_json::Value::Object(mut object) = v else {
+            return Err(DecodeError::expected("an object", &v));
+        };
+        let Some(serde_json::Value::String(tag)) = object.remove(VARIANT_TAG) else {
+            return Err(DecodeError(format!(
+                "expected a case name as `{}`",
+                VARIANT_TAG
+            )));
+        };
+        let case = ty
+            .cases()
+            .find(|case| case.name == tag)
+            .ok_or_else(|| DecodeError(format!("unknown case `{}`", tag)))?;
+        let value = match case.ty {
+            Some(payload) => {
+                let v = object.remove(VARIANT_VALUE).ok_or_else(|| {
+                    DecodeError(format!("case `{}`: missing `{}`", tag, VARIANT_VALUE))
+                })?;
+
+                Some(
+                    Self::from_json(v, &payload, options)
+                        .map_err(|e| DecodeError(format!("case `{}`: {}", tag, e)))?
+                        .0,
+                )
+            }
+            None => None,
+        };
+
+        ty.new_val(&tag, value)
+            .map_err(|e| DecodeError(format!("{:#}", e)))
+    }
+
+    fn result_from_json(
+        v: serde_json::Value,
+        ty: &wasmtime::component::types::ResultType,
+        options: &ValueOptions,
+    ) -> Result<Val, DecodeError> {
+        let serde_json::Value::Object(mut object) = v else {
+            return Err(DecodeError::expected("an object", &v));
+        };
+        let (case, payload, v) = match (object.remove(RESULT_OK), object.remove(RESULT_ERR)) {
+            (Some(v), None) => (RESULT_OK, ty.ok(), v),
+            (None, Some(v)) => (RESULT_ERR, ty.err(), v),
+            _ => {
+                return Err(DecodeError(format!(
+                    "expected either `{}` or `{}`",
+                    RESULT_OK, RESULT_ERR
+                )))
+            }
+        };
+        let value = match payload {
+            Some(payload) => Some(
+                Self::from_json(v, &payload, options)
+                    .map_err(|e| DecodeError(format!("`{}`: {}", case, e)))?
+                    .0,
+            ),
+            None => None,
+        };
+
+        ty.new_val(match case {
+            RESULT_OK => Ok(value),
+            _ => Err(value),
+        })
+        .map_err(|e| DecodeError(format!("{:#}", e)))
+    }
+
+    fn flags_from_json(
+        v: serde_json::Value,
+        ty: &wasmtime::component::types::Flags,
+    ) -> Result<Val, DecodeError> {
+        let serde_json::Value::Array(items) = v else {
+            return Err(DecodeError::expected("an array of flag names", &v));
+        };
+        let names = items
+            .iter()
+            .map(|item| match item.as_str() {
+                Some(name) if ty.names().any(|flag| flag == name) => Ok(name),
+                Some(name) => Err(DecodeError(format!("unknown flag `{}`", name))),
+                None => Err(DecodeError::expected("a flag name", item)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        ty.new_val(&names)
+            .map_err(|e| DecodeError(format!("{:#}", e)))
+    }
+
+    fn enum_from_json(
+        v: &serde_json::Value,
+        ty: &wasmtime::component::types::Enum,
+        options: &ValueOptions,
+    ) -> Result<Val, DecodeError> {
+        let name = match (v, options.enum_as) {
+            (serde_json::Value::String(name), EnumAs::String | EnumAs::Both) => name.as_str(),
+            (serde_json::Value::Number(n), EnumAs::Int | EnumAs::Both) => n
+                .as_u64()
+                .and_then(|index| ty.names().nth(index as usize))
+                .ok_or_else(|| DecodeError(format!("{} is not a valid case index", n)))?,
+            (_, EnumAs::String) => return Err(DecodeError::expected("a case name", v)),
+            (_, EnumAs::Int) => return Err(DecodeError::expected("a case index", v)),
+            (_, EnumAs::Both) => return Err(DecodeError::expected("a case name or index", v)),
+        };
+
+        ty.new_val(name)
+            .map_err(|_| DecodeError(format!("unknown case `{}`", name)))
+    }
+
+    /// Convert a JSON integer, rejecting the values out of the `range` of the WIT type `name`
+    /// rather than wrapping them around.
+    fn integer_from_json<T>(
+        v: &serde_json::Value,
+        name: &str,
+        range: RangeInclusive<T>,
+    ) -> Result<T, DecodeError>
+    where
+        T: TryFrom<i128> + std::fmt::Display,
+    {
+        let n = match v {
+            serde_json::Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+                (Some(n), _) => i128::from(n),
+                (None, Some(n)) => i128::from(n),
+                (None, None) => return Err(DecodeError(format!("expected an integer, got {}", n))),
+            },
+            v => return Err(DecodeError::expected("an integer", v)),
+        };
+
+        Self::integer_in_range(n, name, range)
+    }
+
+    /// A 64-bit integer, given as a number or, with `--bigint-as-string`, as a string.
+    fn bigint_from_json<T>(
+        v: &serde_json::Value,
+        name: &str,
+        range: RangeInclusive<T>,
+        options: &ValueOptions,
+    ) -> Result<T, DecodeError>
+    where
+        T: TryFrom<i128> + std::fmt::Display,
+    {
+        match v {
+            serde_json::Value::String(s) if options.bigint_as_string => {
+                let n = s.trim().parse::<i128>().map_err(|_| {
+                    DecodeError(format!("expected an integer, got the string {:?}", s))
+                })?;
+
+                Self::integer_in_range(n, name, range)
+            }
+            v => Self::integer_from_json(v, name, range),
+        }
+    }
+
+    fn integer_in_range<T>(n: i128, name: &str, range: RangeInclusive<T>) -> Result<T, DecodeError>
+    where
+        T: TryFrom<i128> + std::fmt::Display,
+    {
+        T::try_from(n).map_err(|_| {
+            DecodeError(format!(
+                "expected {} {}..={}, got {}",
+                name,
+                range.start(),
+                range.end(),
+                n
+            ))
+        })
+    }
+
+    /// A string of exactly one Unicode scalar value, e.g. `"λ"`.
+    fn char_from_json(v: &serde_json::Value) -> Result<char, DecodeError> {
+        let s = v
+            .as_str()
+            .ok_or_else(|| DecodeError::expected("a character", v))?;
+        let mut chars = s.chars();
+
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            (None, _) => Err(DecodeError(String::from(
+                "expected a character, got an empty string",
+            ))),
+            (Some(_), Some(_)) => Err(DecodeError(format!(
+                "expected a single character, got {} characters",
+                s.chars().count()
+            ))),
+        }
+    }
+
+    fn float32_from_json(
+        v: &serde_json::Value,
+        options: &ValueOptions,
+    ) -> Result<f32, DecodeError> {
+        let n = Self::float_from_json(v, options)?;
+        let narrowed = n as f32;
+
+        if options.strict_floats {
+            if narrowed.is_infinite() && n.is_finite() {
+                return Err(DecodeError(format!("{} is out of range for a float32", v)));
+            }
+
+            // NaN is never equal to itself, but is still represented by a float32
+            if !n.is_nan() && narrowed as f64 != n {
+                return Err(DecodeError(format!(
+                    "{} can't be represented exactly as a float32",
+                    v
+                )));
+            }
+        }
+
+        Ok(narrowed)
+    }
+
+    fn float_from_json(v: &serde_json::Value, options: &ValueOptions) -> Result<f64, DecodeError> {
+        match v {
+            serde_json::Value::Number(n) => Ok(n.as_f64().unwrap()),
+            serde_json::Value::String(s) if options.coerce_strings => {
+                let n = s
+                    .trim()
+                    .parse::<f64>()
+                    .map_err(|_| DecodeError(format!("`{}` is not a valid number", s)))?;
+
+                // Only explicit infinities are accepted, not overflowing values like "1e400"
+                if n.is_infinite() && !s.to_lowercase().contains("inf") {
+                    return Err(DecodeError(format!("`{}` is out of range", s)));
+                }
+
+                Ok(n)
+            }
+            v => Err(DecodeError::expected("a number", v)),
+        }
+    }
+
+    fn float_to_json(v: f64, options: &ValueOptions) -> serde_json::Value {
+        match Number::from_f64(v) {
+            Some(n) => serde_json::Value::Number(n),
+            None => match options.non_finite {
+                NonFinitePolicy::Null => serde_json::Value::Null,
+                NonFinitePolicy::String if v.is_nan() => serde_json::Value::from("NaN"),
+                NonFinitePolicy::String if v > 0.0 => serde_json::Value::from("Infinity"),
+                NonFinitePolicy::String => serde_json::Value::from("-Infinity"),
+            },
+        }
+    }
+
+    pub fn to_json(&self, options: &ValueOptions) -> serde_json::Value {
+        Self::val_to_json(&self.0, options)
+    }
+
+    fn val_to_json(v: &Val, options: &ValueOptions) -> serde_json::Value {
+        match v {
+            Val::Bool(v) => serde_json::Value::Bool(*v),
+            Val::S8(v) => serde_json::Value::Number(Number::from(*v)),
+            Val::U8(v) => serde_json::Value::Number(Number::from(*v)),
+            Val::S16(v) => serde_json::Value::Number(Number::from(*v)),
+            Val::U16(v) => serde_json::Value::Number(Number::from(*v)),
+            Val::S32(v) => serde_json::Value::Number(Number::from(*v)),
+            Val::U32(v) => serde_json::Value::Number(Number::from(*v)),
+            Val::S64(v) if options.bigint_as_string => serde_json::Value::from(v.to_string()),
+            Val::U64(v) if options.bigint_as_string => serde_json::Value::from(v.to_string()),
+            Val::S64(v) => serde_json::Value::Number(Number::from(*v)),
+            Val::U64(v) => serde_json::Value::Number(Number::from(*v)),
+            Val::Float32(v) => Self::float_to_json(*v as f64, options),
+            Val::Float64(v) => Self::float_to_json(*v, options),
+            Val::Char(v) => serde_json::Value::String(v.clone().to_string()),
+            Val::String(v) => serde_json::Value::String(v.clone().into_string()),
+            Val::List(v) => v.iter().map(|v| Self::val_to_json(v, options)).collect(),
+            Val::Record(v) => v
+                .fields()
+                .map(|(name, v)| (name.to_string(), Self::val_to_json(v, options)))
+                .collect(),
+            Val::Tuple(v) => v
+                .values()
+                .iter()
+                .map(|v| Self::val_to_json(v, options))
+                .collect(),
+            Val::Variant(v) => {
+                let mut object = serde_json::Map::new();
+
+                object.insert(VARIANT_TAG.to_string(), v.discriminant().into());
+                if let Some(payload) = v.payload() {
+                    object.insert(
+                        VARIANT_VALUE.to_string(),
+                        Self::val_to_json(payload, options),
+                    );
+                }
+
+                serde_json::Value::Object(object)
+            }
+            Val::Enum(v) => match options.enum_as {
+                EnumAs::Int => serde_json::Value::from(
+                    v.ty()
+                        .names()
+                        .position(|name| name == v.discriminant())
+                        .unwrap(),
+                ),
+                EnumAs::String | EnumAs::Both => serde_json::Value::from(v.discriminant()),
+            },
+            Val::Option(v) => v
+                .value()
+                .map_or(serde_json::Value::Null, |v| Self::val_to_json(v, options)),
+            Val::Result(v) => {
+                let (case, value) = match v.value() {
+                    Ok(value) => (RESULT_OK, value),
+                    Err(value) => (RESULT_ERR, value),
+                };
+                let value =
+                    value.map_or(serde_json::Value::Null, |v| Self::val_to_json(v, options));
+
+                serde_json::json!({ case: value })
+            }
+            Val::Flags(v) => v.flags().map(serde_json::Value::from).collect(),
+            // A resource is a handle owned by the instance, which has no JSON value
+            Val::Resource(_) => serde_json::Value::Null,
+        }
+    }
+}
+
+/// The results of a call, apart from the instance it ran in.
+struct Outcome {
+    results: wasmtime::Result<Vec<Val>>,
+    /// The types of the results of the function.
+    types: Vec<wasmtime::component::Type>,
+    /// The fuel consumed by the call, when a budget was set.
+    consumed: Option<u64>,
+    /// What the call wrote to the standard error, with `--echo-stderr`.
+    stderr: Option<Vec<u8>>,
+}
+
+impl Endpoint {
+    /// Call the function, on a thread of the blocking pool so that a slow guest, or a wait for an
+    /// idle instance, doesn't hold the worker serving the other requests.
+    pub(crate) async fn call(
+        &self,
+        request: &HttpRequest,
+        payload: Option<Body>,
+        verbose_errors: bool,
+    ) -> Result<HttpResponse, ApiError> {
+        request.extensions_mut().insert(CallLog {
+            function: self.operation_id(),
+            trapped: false,
+        });
+
+        let _guard = self.limits.acquire()?;
+        let budget = match self.max_fuel {
+            Some(max) => Some(fuel::budget(request, max)?),
+            None => None,
+        };
+        let metrics = request.app_data::<web::Data<Metrics>>().cloned();
+        let endpoint = self.clone();
+        let mut outcome =
+            web::block(move || endpoint.run(payload, budget, metrics.as_deref().map(Arc::as_ref)))
+                .await
+                // The instance a panic occurred in is discarded by the pool
+                .unwrap_or_else(|_| Err(panicked()))?;
+
+        if let Err(e) = &outcome.results {
+            if let Some(log) = request.extensions_mut().get_mut::<CallLog>() {
+                log.trapped = e.downcast_ref::<Trap>().is_some();
+            }
+        }
+
+        let stderr = outcome
+            .stderr
+            .take()
+            .map(|stderr| wasi::stderr_header(&stderr));
+        // A panic fails the request alone instead of the worker serving it
+        let response = panic::catch_unwind(AssertUnwindSafe(|| {
+            self.respond(request, outcome, verbose_errors)
+        }))
+        .unwrap_or_else(|_| Err(panicked()));
+
+        match (stderr, response) {
+            (Some((name, value)), Ok(mut response)) => {
+                response.headers_mut().insert(name, value);
+                Ok(response)
+            }
+            (Some(header), Err(e)) => Err(e.header(header)),
+            (None, response) => response,
+        }
+    }
+
+    /// Call the function in an instance taken from the pool, waiting for one if they are all
+    /// busy.
+    fn run(
+        &self,
+        payload: Option<Body>,
+        budget: Option<u64>,
+        metrics: Option<&Metrics>,
+    ) -> Result<Outcome, ApiError> {
+        let mut store = self.pool.get().map_err(|e| {
+            log::error!("{:#}", e);
+            ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "the component couldn't be instantiated",
+            )
+        })?;
+        let callable = self.callable(&mut store);
+        let parameters = self.decode_parameters(&callable, store.as_context(), payload)?;
+
+        if let Some(budget) = budget {
+            store.set_fuel(budget).unwrap();
+        }
+
+        // Only what the call writes is echoed, not what the instance wrote before
+        store.data_mut().take_stderr();
+        let start = Instant::now();
+        let results = self.invoke(&callable, &mut store, &parameters);
+        if let Some(metrics) = metrics {
+            metrics.record(&self.operation_id(), start.elapsed(), results.is_err());
+        }
+        let consumed = budget.map(|budget| budget - store.get_fuel().unwrap_or(0));
+
+        if results.is_err() {
+            // A trap leaves the instance in a state it can't be called in anymore
+            store.discard();
+        }
+
+        Ok(Outcome {
+            results,
+            types: callable.results(store.as_context()).to_vec(),
+            consumed,
+            stderr: store.data_mut().take_stderr(),
+        })
+    }
+
+    /// The HTTP response of a call.
+    fn respond(
+        &self,
+        request: &HttpRequest,
+        outcome: Outcome,
+        verbose_errors: bool,
+    ) -> Result<HttpResponse, ApiError> {
+        let Outcome {
+            results: res,
+            types,
+            consumed,
+            ..
+        } = outcome;
+
+        match res {
+            Ok(mut results) => {
+                if let Some(list) = self.streamed_list(request, &mut results) {
+                    let mut response = HttpResponse::build(self.success_status(true));
+
+                    if let Some(consumed) = consumed {
+                        response.insert_header(fuel::consumed_header(consumed));
+                    }
+
+                    return Ok(response
+                        .content_type(ContentType::json())
+                        .body(ListBody::new(list, self.options.clone())));
+                }
+
+                let to_json = |value: &Val| Value(value.clone()).to_json(&self.options);
+                let (status, result, ty) = match (&results[..], &self.prototype.results) {
+                    ([], _) => (self.success_status(false), None, None),
+                    ([value], wit_parser::Results::Anon(_)) => {
+                        let ty = types[0].clone();
+
+                        // A returned result is split into a success and an error response
+                        match (value, ty) {
+                            (Val::Result(result), wasmtime::component::Type::Result(ty)) => {
+                                match result.value() {
+                                    Ok(Some(value)) => {
+                                        (self.success_status(true), Some(to_json(value)), ty.ok())
+                                    }
+                                    Ok(None) => (self.success_status(false), None, None),
+                                    Err(value) => (
+                                        self.options.result_error_status(),
+                                        value.map(to_json),
+                                        ty.err(),
+                                    ),
+                                }
+                            }
+                            (value, ty) => {
+                                (self.success_status(true), Some(to_json(value)), Some(ty))
+                            }
+                        }
+                    }
+                    (results, _) => (
+                        self.success_status(true),
+                        Some(self.results_to_json(results)),
+                        None,
+                    ),
+                };
+                let mut response = HttpResponse::build(status);
+
+                if let Some(consumed) = consumed {
+                    response.insert_header(fuel::consumed_header(consumed));
+                }
+
+                let Some(mut result) = result else {
+                    return Ok(response.finish());
+                };
+
+                if let (Some(policy), Some(ty)) = (self.options.field_selection, &ty) {
+                    if let Some(fields) = fields::requested(request).filter(|_| status.is_success())
+                    {
+                        fields::select(&mut result, ty, &fields, policy)?;
+                    }
+                }
+
+                Ok(response.content_type(ContentType::json()).json(result))
+            }
+            Err(e) => {
+                let error = match e.downcast_ref::<Trap>() {
+                    Some(Trap::OutOfFuel) => fuel::out_of_fuel(),
+                    Some(Trap::Interrupt) => timeout::timed_out(),
+                    // The parameters were valid, so this is a failure of the function itself
+                    _ if verbose_errors => ApiError::new(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("the function call failed: {:#}", e),
+                    ),
+                    _ => ApiError::new(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "the function call failed",
+                    ),
+                };
+
+                Err(match consumed {
+                    Some(consumed) => error.header(fuel::consumed_header(consumed)),
+                    None => error,
+                })
+            }
+        }
+    }
+}
+
+/// The error of a call which panicked.
+fn panicked() -> ApiError {
+    ApiError::new(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "the function call panicked",
+    )
+}
+
+impl Endpoint {
+    /// The list returned by the function, taken out of its results when it's long enough to be
+    /// streamed and the fields of its elements aren't selected.
+    fn streamed_list(&self, request: &HttpRequest, results: &mut Vec<Val>) -> Option<List> {
+        let selected =
+            self.options.field_selection.is_some() && fields::requested(request).is_some();
+
+        match (&results[..], &self.prototype.results) {
+            ([Val::List(list)], wit_parser::Results::Anon(_))
+                if list.len() >= stream::MIN_STREAMED_ITEMS && !selected =>
+            {
+                match results.pop() {
+                    Some(Val::List(list)) => Some(list),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// The function in an instance of the component exporting it.
+    fn callable(&self, instance: &mut PooledInstance) -> Func {
+        instance
+            .func(self.instance.as_deref(), &self.prototype.name)
+            .expect("the function is exported by every instance of its component")
+    }
+
+    /// Call the function with decoded parameters, returning its results.
+    fn invoke(
+        &self,
+        callable: &Func,
+        instance: &mut PooledInstance,
+        parameters: &[Val],
+    ) -> wasmtime::Result<Vec<Val>> {
+        let mut results = vec![Val::Bool(false); self.prototype.results.len()];
+
+        instance.run(|store| {
+            callable.call(store.as_context_mut(), parameters, &mut results)?;
+            callable.post_return(store.as_context_mut())
+        })?;
+
+        Ok(results)
+    }
+
+    /// The JSON results of a call: an object keyed by name for the named results, or the value of
+    /// the single anonymous one, if any.
+    fn results_to_json(&self, results: &[Val]) -> serde_json::Value {
+        match &self.prototype.results {
+            wit_parser::Results::Named(params) if !params.is_empty() => serde_json::Value::Object(
+                params
+                    .iter()
+                    .zip(results)
+                    .map(|((name, _), value)| {
+                        (name.clone(), Value(value.clone()).to_json(&self.options))
+                    })
+                    .collect(),
+            ),
+            _ => results.first().map_or(serde_json::Value::Null, |value| {
+                Value(value.clone()).to_json(&self.options)
+            }),
+        }
+    }
+
+    fn decode_parameters(
+        &self,
+        callable: &Func,
+        store: impl AsContext,
+        payload: Option<Body>,
+    ) -> Result<Vec<Val>, ApiError> {
+        // An empty body is only valid when there is nothing to decode.
+        let mut payload = match payload {
+            Some(payload) => payload,
+            None if self.prototype.params.is_empty() => Body::default(),
+            None => {
+                return Err(ApiError::bad_request(
+                    "expected a JSON object of named parameters, got an empty body",
+                ))
+            }
+        };
+
+        if let Some(values) = payload.positional.take() {
+            let expected = self.prototype.params.len();
+
+            if values.len() != expected {
+                return Err(ApiError::bad_request(format!(
+                    "expected {} positional parameter(s), got {}",
+                    expected,
+                    values.len()
+                )));
+            }
+
+            payload.params = self
+                .prototype
+                .params
+                .iter()
+                .map(|(name, _)| name.clone())
+                .zip(values)
+                .collect();
+        }
+
+        if let Some(key) = payload
+            .duplicates
+            .first()
+            .filter(|_| self.options.reject_duplicate_keys)
+        {
+            return Err(ApiError::bad_request(format!("duplicate key `{}`", key)));
+        }
+
+        let missing = self
+            .prototype
+            .params
+            .iter()
+            .map(|(name, _)| name)
+            .filter(|name| !payload.params.contains_key(name.as_str()))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if !missing.is_empty() {
+            let names = missing
+                .iter()
+                .map(|name| format!("`{}`", name))
+                .collect::<Vec<_>>();
+            let message = match missing.len() {
+                1 => format!("missing parameter {}", names[0]),
+                _ => format!("missing parameters {}", names.join(", ")),
+            };
+
+            return Err(ApiError::bad_request(message).detail("parameters", missing));
+        }
+
+        if let Some(validator) = &self.validator {
+            let mut params = payload.params.clone();
+            // The query parameter selecting the fields of the results isn't a parameter
+            if self.options.field_selection.is_some() {
+                params.remove(fields::FIELDS);
+            }
+
+            let errors = validation::errors(validator, &serde_json::to_value(params).unwrap());
+            if !errors.is_empty() {
+                return Err(
+                    ApiError::bad_request("the parameters don't match their schemas")
+                        .detail("errors", errors),
+                );
+            }
+        }
+
+        self.prototype
+            .params
+            .iter()
+            .zip(callable.params(store).iter())
+            .map(|((name, _), ty)| {
+                let v = payload.params.remove(name).unwrap_or_default();
+
+                Value::from_json(v, ty, &self.options)
+                    .map(|v| v.0)
+                    .map_err(|e| ApiError::bad_request(format!("parameter `{}`: {}", name, e)))
+            })
+            .collect()
+    }
+
+    /// The status of the success responses, with a body or not.
+    fn success_status(&self, body: bool) -> StatusCode {
+        match (self.status, body) {
+            (Some(status), _) => status,
+            (None, true) => StatusCode::OK,
+            (None, false) => StatusCode::NO_CONTENT,
+        }
+    }
+
+    /// Whether the success responses have a body, i.e. the function returns a value other than
+    /// the empty success case of a result.
+    fn returns_body(&self) -> bool {
+        match self.result_type() {
+            Some(result) => result.ok.is_some(),
+            None => self.prototype.results.len() > 0,
+        }
+    }
+
+    /// The validator of the object of named parameters, closing the objects of its schema to the
+    /// unknown properties.
+    fn parameters_validator(&self) -> anyhow::Result<JSONSchema> {
+        let mut named = BTreeMap::new();
+        let mut properties = serde_json::Map::new();
+
+        for (name, ty) in &self.prototype.params {
+            Type(*ty).collect_named(&self.resolve, &mut named);
+
+            let schema = Type(*ty).into_schema(&self.resolve, &*self.mapper);
+            properties.insert(name.clone(), serde_json::to_value(schema)?);
+        }
+
+        let schemas = named
+            .into_iter()
+            .map(|(name, id)| {
+                let mut schema =
+                    serde_json::to_value(Type::definition(&self.resolve, id, &*self.mapper))?;
+                validation::close_objects(&mut schema);
+
+                Ok((name, schema))
+            })
+            .collect::<anyhow::Result<serde_json::Map<_, _>>>()?;
+        let mut schema = serde_json::json!({ "type": "object", "properties": properties });
+        validation::close_objects(&mut schema);
+
+        validation::validator(
+            &schema,
+            &serde_json::json!({ "components": { "schemas": schemas } }),
+        )
+    }
+
+    /// The request body, an object of named parameters or an array of positional ones.
+    fn function_request_body(&self) -> RequestBody {
+        let params = self.param_descriptions();
+        let named = self
+            .prototype
+            .params
+            .iter()
+            .fold(ObjectBuilder::new(), |obj, (name, ty)| {
+                let schema = Type(*ty).into_schema(&self.resolve, &*self.mapper);
+
+                match params.get(name.as_str()) {
+                    Some(description) => obj.property(name, with_description(schema, description)),
+                    None => obj.property(name, schema),
+                }
+            })
+            .build();
+        let schema = match self.prototype.params.is_empty() {
+            true => Schema::Object(named),
+            false => {
+                let names = self
+                    .prototype
+                    .params
+                    .iter()
+                    .map(|(name, _)| format!("`{}`", name))
+                    .collect::<Vec<_>>();
+                let positional = ArrayBuilder::new()
+                    .items(Schema::AnyOf(
+                        self.prototype
+                            .params
+                            .iter()
+                            .fold(AnyOfBuilder::new(), |any_of, (_, ty)| {
+                                any_of.item(Type(*ty).into_schema(&self.resolve, &*self.mapper))
+                            })
+                            .build(),
+                    ))
+                    .min_items(Some(self.prototype.params.len()))
+                    .max_items(Some(self.prototype.params.len()))
+                    .description(Some(format!(
+                        "The parameters in order: {}",
+                        names.join(", ")
+                    )))
+                    .build();
+
+                Schema::OneOf(OneOfBuilder::new().item(named).item(positional).build())
+            }
+        };
+
+        RequestBodyBuilder::new()
+            .content(
+                ContentType::json().to_string(),
+                ContentBuilder::new()
+                    .schema(schema)
+                    .example(self.request_example.clone())
+                    .build(),
+            )
+            .build()
+    }
+
+    /// The parameters of a GET request, given in its query string as JSON values, or as is for
+    /// the string parameters (e.g. `?name=Alice&age=42`).
+    pub(crate) fn query_parameters(&self, request: &HttpRequest) -> Result<Body, ApiError> {
+        let strings = self
+            .prototype
+            .params
+            .iter()
+            .filter(|(_, ty)| matches!(Type(*ty).kind(&self.resolve), "string" | "char" | "enum"))
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+
+        Body::from_query(request.query_string(), &strings).map_err(ApiError::bad_request)
+    }
+
+    /// The query parameters of a GET endpoint.
+    fn function_parameters(&self) -> Vec<Parameter> {
+        let params = self.param_descriptions();
+
+        self.prototype
+            .params
+            .iter()
+            .map(|(name, ty)| {
+                ParameterBuilder::new()
+                    .name(name)
+                    .parameter_in(ParameterIn::Query)
+                    .required(Required::True)
+                    .description(params.get(name.as_str()).copied())
+                    .schema(Some(Type(*ty).into_schema(&self.resolve, &*self.mapper)))
+                    .example(
+                        self.request_example
+                            .as_ref()
+                            .and_then(|example| example.get(name))
+                            .cloned(),
+                    )
+                    .build()
+            })
+            .collect()
+    }
+
+    /// The parameter descriptions, from the `@param <name> <description>` annotations.
+    fn param_descriptions(&self) -> HashMap<&str, &str> {
+        annotations(&self.prototype)
+            .filter(|(tag, _)| *tag == "param")
+            .filter_map(|(_, value)| {
+                let (name, description) = value.split_once(char::is_whitespace)?;
+
+                Some((name, description.trim()))
+            })
+            .collect()
+    }
+
+    /// Interface functions are prefixed with their interface name, which can't clash with a root
+    /// function since WIT identifiers can't contain a dot.
+    fn operation_id(&self) -> String {
+        let name = match &self.interface {
+            Some(interface) => format!("{}.{}", interface, self.prototype.name),
+            None => self.prototype.name.clone(),
+        };
+
+        match &self.component {
+            Some(component) => format!("{}.{}", component, name),
+            None => name,
+        }
+    }
+
+    fn parse_function_docs(&self) -> (String, Option<String>) {
+        let docs = self.prototype.docs.contents.clone().unwrap_or_default();
+        let mut lines = docs.lines().filter(|line| !line.trim().starts_with('@'));
+        let summary = lines.next().unwrap_or_default();
+
+        let description = lines
+            .skip_while(|line| line.trim().is_empty()) // Skip any empty lines after the summary
+            .collect::<Vec<&str>>()
+            .join("\n");
+
+        let description = if description.is_empty() {
+            None
+        } else {
+            Some(description)
+        };
+
+        (summary.into(), description)
+    }
+
+    /// The result type returned by the function, if any, which is split into a success and an
+    /// error response.
+    fn result_type(&self) -> Option<&wit_parser::Result_> {
+        let wit_parser::Results::Anon(mut ty) = self.prototype.results else {
+            return None;
+        };
+
+        while let wit_parser::Type::Id(id) = ty {
+            match &self.resolve.types[id].kind {
+                TypeDefKind::Result(result) => return Some(result),
+                TypeDefKind::Type(alias) => ty = *alias,
+                _ => return None,
+            }
+        }
+
+        None
+    }
+
+    fn result_schema(&self) -> RefOr<Schema> {
+        match &self.prototype.results {
+            wit_parser::Results::Named(params) => RefOr::T(Schema::Object(
+                params
+                    .iter()
+                    .fold(ObjectBuilder::new(), |obj, (name, ty)| {
+                        obj.property(name, Type(*ty).into_schema(&self.resolve, &*self.mapper))
+                            .required(name)
+                    })
+                    .build(),
+            )),
+            wit_parser::Results::Anon(ty) => Type(*ty).into_schema(&self.resolve, &*self.mapper),
+        }
+    }
+}
+
+impl From<Endpoint> for Operation {
+    fn from(endpoint: Endpoint) -> Self {
+        let (summary, description) = endpoint.parse_function_docs();
+
+        let operation = OperationBuilder::new()
+            .operation_id(Some(endpoint.operation_id()))
+            .summary(Some(summary))
+            .description(description)
+            .tag(endpoint.tag.name.clone());
+        let operation = match endpoint.method {
+            PathItemType::Get if endpoint.prototype.params.is_empty() => operation,
+            PathItemType::Get => operation.parameters(Some(endpoint.function_parameters())),
+            _ => operation.request_body(Some(endpoint.function_request_body())),
+        };
+
+        match endpoint.result_type() {
+            Some(result) => {
+                let schema = |ty: Option<wit_parser::Type>| {
+                    ty.map(|ty| Type(ty).into_schema(&endpoint.resolve, &*endpoint.mapper))
+                };
+                let success = endpoint.success_status(result.ok.is_some());
+
+                operation
+                    .response(
+                        success.as_str(),
+                        json_response(schema(result.ok), endpoint.response_example.clone()),
+                    )
+                    .response(
+                        endpoint.options.result_error_status().as_str(),
+                        json_response(schema(result.err), None),
+                    )
+            }
+            None if endpoint.prototype.results.len() == 0 => operation.response(
+                endpoint.success_status(false).as_str(),
+                json_response(None, None),
+            ),
+            None => operation.response(
+                endpoint.success_status(true).as_str(),
+                json_response(
+                    Some(endpoint.result_schema()),
+                    endpoint.response_example.clone(),
+                ),
+            ),
+        }
+        .build()
+    }
+}
+
+/// A JSON response with its example if any, or an empty one without a schema.
+fn json_response(schema: Option<RefOr<Schema>>, example: Option<serde_json::Value>) -> Response {
+    let response = ResponseBuilder::new();
+
+    match schema {
+        Some(schema) => response.content(
+            ContentType::json().to_string(),
+            ContentBuilder::new()
+                .schema(schema)
+                .example(example)
+                .build(),
+        ),
+        None => response,
+    }
+    .build()
+}
+
+impl From<Endpoint> for PathItem {
+    fn from(endpoint: Endpoint) -> Self {
+        let method = endpoint.method.clone();
+        let operation: Operation = endpoint.into();
+
+        PathItemBuilder::new().operation(method, operation).build()
+    }
+}
+
+/// A function exported by a component, either at the world root or in an interface.
+struct ExportedFunction<'a> {
+    /// The name of the exporting component, when serving several ones.
+    component: Option<String>,
+    /// The short name of the exporting interface, if any.
+    interface: Option<String>,
+    /// The name of the component instance exporting the interface, if any.
+    instance: Option<String>,
+    /// The name of the exporting world, or of the component file when the world is unnamed.
+    world: String,
+    /// The docs of the exporting interface, or of the world for the root functions.
+    owner_docs: Option<&'a str>,
+    function: &'a Function,
+}
+
+impl ExportedFunction<'_> {
+    /// The tag named after the interface, qualified by the component when serving several ones,
+    /// or after the component (or its world) for the root functions.
+    fn tag(&self) -> Tag {
+        let name = match (&self.component, &self.interface) {
+            (Some(component), Some(interface)) => format!("{}.{}", component, interface),
+            (None, Some(name)) | (Some(name), None) => name.clone(),
+            (None, None) => self.world.clone(),
+        };
+
+        TagBuilder::new()
+            .name(name)
+            .description(self.owner_docs.map(str::trim))
+            .build()
+    }
+
+    fn path(&self, options: &PathOptions) -> String {
+        let name = options.path_case.apply(&self.function.name);
+        let interface = self
+            .interface
+            .as_deref()
+            .map(|interface| options.path_case.apply(interface));
+        // The functions are qualified by their interface, unless the template places it
+        let func = match &interface {
+            Some(interface) if !options.path_template.contains(PATH_INTERFACE) => {
+                format!("{}{}{}", interface, options.path_separator, name)
+            }
+            _ => name,
+        };
+        let path = options
+            .path_template
+            .replace(PATH_WORLD, &options.path_case.apply(&self.world))
+            .replace(PATH_INTERFACE, interface.as_deref().unwrap_or_default())
+            .replace(PATH_FUNC, &func);
+        // The segments left empty by the root functions, which have no interface
+        let path = path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        match &self.component {
+            Some(component) => format!("/{}/{}", component, path),
+            None => format!("/{}", path),
+        }
+    }
+}
+
+/// Options of the `info` object of the OpenAPI definition.
+#[derive(Args, Debug, Clone)]
+struct InfoOptions {
+    /// Specify the title of the API, overriding the one returned by `openapi-info` [default: WASM Component API]
+    #[clap(long)]
+    title: Option<String>,
+
+    /// Specify the version of the API, overriding the one returned by `openapi-info` [default: 1.0]
+    #[clap(long)]
+    api_version: Option<String>,
+
+    /// Specify the description of the API, overriding the one returned by `openapi-info`
+    /// [default: OpenAPI definition of a WASM component.]
+    #[clap(long)]
+    api_description: Option<String>,
+}
+
+impl InfoOptions {
+    /// Fill the options missing from the command line with the info returned by the component.
+    fn fill(&mut self, info: info::Info) {
+        self.title.get_or_insert(info.title);
+        self.api_version.get_or_insert(info.version);
+        self.api_description.get_or_insert(info.description);
+    }
+}
+
+/// Options of the routes generated from the exported functions.
+#[derive(Args, Debug, Clone)]
+struct PathOptions {
+    /// Specify the casing of the interface and function names in the routes
+    #[clap(long, value_enum, default_value_t = PathCase::Kebab)]
+    path_case: PathCase,
+
+    /// Specify the separator between the interface and function names in the routes
+    #[clap(long, default_value = "/", value_parser = parse_path_separator)]
+    path_separator: String,
+
+    /// Specify the routes with the `{world}`, `{interface}` and `{func}` placeholders, the
+    /// function names being qualified by their interface when `{interface}` isn't used
+    #[clap(long, default_value = "/{func}", value_parser = parse_path_template)]
+    path_template: String,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum PathCase {
+    /// `add-numbers`, as declared in WIT
+    Kebab,
+    /// `add_numbers`
+    Snake,
+    /// `addNumbers`
+    Camel,
+}
+
+impl PathCase {
+    /// Convert a kebab-case WIT identifier.
+    fn apply(self, name: &str) -> String {
+        match self {
+            PathCase::Kebab => name.to_string(),
+            PathCase::Snake => name.replace('-', "_"),
+            PathCase::Camel => name
+                .split('-')
+                .enumerate()
+                .map(|(i, word)| match (i, word.chars().next()) {
+                    (0, _) | (_, None) => word.to_string(),
+                    (_, Some(first)) => first.to_uppercase().chain(word.chars().skip(1)).collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+fn parse_path_separator(s: &str) -> anyhow::Result<String> {
+    if s.is_empty()
+        || !s
+            .chars()
+            .all(|c| matches!(c, '/' | '.' | '_' | '-' | '~' | ':'))
+    {
+        bail!("expected one or more of `/`, `.`, `_`, `-`, `~` and `:`");
+    }
+
+    Ok(s.to_string())
+}
+
+fn parse_path_template(s: &str) -> anyhow::Result<String> {
+    if !s.starts_with('/') || !s.contains(PATH_FUNC) {
+        bail!(
+            "expected a path starting with `/` and containing `{}`",
+            PATH_FUNC
+        );
+    }
+
+    let rest = [PATH_WORLD, PATH_INTERFACE, PATH_FUNC]
+        .iter()
+        .fold(s.to_string(), |s, placeholder| s.replace(placeholder, ""));
+    if rest.contains(['{', '}']) {
+        bail!(
+            "expected no other placeholder than `{}`, `{}` and `{}`",
+            PATH_WORLD,
+            PATH_INTERFACE,
+            PATH_FUNC
+        );
+    }
+
+    Ok(s.to_string())
+}
+
+fn list_wasm_component_functions(
+    wit: &DecodedWasm,
+    component: Option<String>,
+    file_name: Option<String>,
+) -> Vec<ExportedFunction<'_>> {
+    let resolve = wit.resolve();
+    let world_name = |world: &World| match (world.name.as_str(), &file_name) {
+        (DECODED_WORLD, Some(name)) => name.clone(),
+        (name, _) => name.to_string(),
+    };
+
+    // Find the exported functions, at the world root first, then in the exported interfaces
+    let functions = resolve.worlds.iter().flat_map(|(_id, world)| {
+        let root = world.exports.iter().filter_map(|(_, item)| match item {
+            WorldItem::Function(function) => Some(ExportedFunction {
+                component: component.clone(),
+                interface: None,
+                instance: None,
+                world: world_name(world),
+                owner_docs: world.docs.contents.as_deref(),
+                function,
+            }),
+            _ => None,
+        });
+        let interfaces = world.exports.iter().filter_map(|(key, item)| match item {
+            WorldItem::Interface(id) => Some((key, &resolve.interfaces[*id])),
+            _ => None,
+        });
+        let component = &component;
+        let interfaces = interfaces.flat_map(move |(key, interface)| {
+            let name = match key {
+                WorldKey::Name(name) => name.clone(),
+                WorldKey::Interface(_) => interface.name.clone().unwrap_or_default(),
+            };
+            let instance = resolve.name_world_key(key);
+            let component = component.clone();
+
+            interface
+                .functions
+                .values()
+                .map(move |function| ExportedFunction {
+                    component: component.clone(),
+                    interface: Some(name.clone()),
+                    instance: Some(instance.clone()),
+                    world: world_name(world),
+                    owner_docs: interface.docs.contents.as_deref(),
+                    function,
+                })
+        });
+
+        root.chain(interfaces)
+    });
+
+    functions.collect()
+}
+
+fn get_endpoints(
+    resolve: &Arc<Resolve>,
+    functions: Vec<ExportedFunction>,
+    pool: &Arc<InstancePool>,
+    max_fuel: Option<u64>,
+    path_options: &PathOptions,
+    options: &Arc<ValueOptions>,
+    mapper: &Arc<dyn SchemaMapper>,
+) -> anyhow::Result<Vec<Endpoint>> {
+    let mut endpoints = vec![];
+    let mut instance = pool.get()?;
+
+    for exported in functions {
+        let callable = instance.func(exported.instance.as_deref(), &exported.function.name);
+
+        let path = exported.path(path_options);
+        let callable = callable
+            .ok_or_else(|| anyhow!("No exported function matches the `{}` endpoint", path))?;
+        check_signature(resolve, exported.function, &callable, &*instance)
+            .with_context(|| format!("Invalid `{}` endpoint", path))?;
+
+        endpoints.push(Endpoint::new(
+            &exported,
+            path_options,
+            max_fuel,
+            options.clone(),
+            resolve.clone(),
+            mapper.clone(),
+            pool.clone(),
+        )?)
+    }
+
+    Ok(endpoints)
+}
+
+/// Check the runtime function has the parameter and result types of its WIT prototype, which
+/// the schemas and JSON conversions are based on.
+fn check_signature(
+    resolve: &Resolve,
+    prototype: &Function,
+    callable: &wasmtime::component::Func,
+    store: impl AsContext,
+) -> anyhow::Result<()> {
+    let params = callable.params(&store);
+    let results = callable.results(&store);
+
+    if params.len() != prototype.params.len() {
+        bail!(
+            "the function takes {} parameters, but {} in its WIT declaration",
+            params.len(),
+            prototype.params.len()
+        );
+    }
+    if results.len() != prototype.results.len() {
+        bail!(
+            "the function returns {} results, but {} in its WIT declaration",
+            results.len(),
+            prototype.results.len()
+        );
+    }
+
+    let params = prototype
+        .params
+        .iter()
+        .map(|(name, ty)| (format!("parameter `{}`", name), ty))
+        .zip(params.iter());
+    let results = prototype
+        .results
+        .iter_types()
+        .enumerate()
+        .map(|(i, ty)| (format!("result #{}", i), ty))
+        .zip(results.iter());
+
+    for ((what, expected), actual) in params.chain(results) {
+        let expected = Type(*expected).kind(resolve);
+        let actual = runtime_kind(actual);
+
+        if expected != actual {
+            bail!(
+                "{} is a {}, but a {} in its WIT declaration",
+                what,
+                actual,
+                expected
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// The kind of a runtime type, named like [`Type::kind`].
+fn runtime_kind(ty: &wasmtime::component::Type) -> &'static str {
+    use wasmtime::component::Type;
+
+    match ty {
+        Type::Bool => "bool",
+        Type::S8 => "s8",
+        Type::U8 => "u8",
+        Type::S16 => "s16",
+        Type::U16 => "u16",
+        Type::S32 => "s32",
+        Type::U32 => "u32",
+        Type::S64 => "s64",
+        Type::U64 => "u64",
+        Type::Float32 => "float32",
+        Type::Float64 => "float64",
+        Type::Char => "char",
+        Type::String => "string",
+        Type::List(_) => "list",
+        Type::Record(_) => "record",
+        Type::Tuple(_) => "tuple",
+        Type::Variant(_) => "variant",
+        Type::Enum(_) => "enum",
+        Type::Option(_) => "option",
+        Type::Result(_) => "result",
+        Type::Flags(_) => "flags",
+        Type::Own(_) | Type::Borrow(_) => "handle",
+    }
+}
+
+/// Build the OpenAPI declaration of the endpoints, whose schemas are namespaced by component when
+/// they are exported by several ones (e.g. `calc.point`), for their types not to collide.
+fn openapi(endpoints: &[Endpoint], info: &InfoOptions) -> OpenApiBuilder {
+    let mut components = Vec::<&str>::new();
+    for component in endpoints
+        .iter()
+        .filter_map(|endpoint| endpoint.component.as_deref())
+    {
+        if !components.contains(&component) {
+            components.push(component);
+        }
+    }
+
+    let openapi = match components.len() {
+        0 | 1 => declare(endpoints),
+        _ => merge(endpoints, &components),
+    };
+
+    openapi.info(
+        InfoBuilder::new()
+            .title(info.title.as_deref().unwrap_or("WASM Component API"))
+            .version(info.api_version.as_deref().unwrap_or("1.0"))
+            .description(Some(
+                info.api_description
+                    .as_deref()
+                    .unwrap_or("OpenAPI definition of a WASM component."),
+            ))
+            .build(),
+    )
+}
+
+/// The declarations of the endpoints of each component, merged with their schemas namespaced by
+/// component.
+fn merge(endpoints: &[Endpoint], components: &[&str]) -> OpenApiBuilder {
+    let mut merged = serde_json::Value::Null;
+
+    for component in components {
+        let endpoints = endpoints
+            .iter()
+            .filter(|endpoint| endpoint.component.as_deref() == Some(component))
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut spec = serde_json::to_value(declare(&endpoints).build()).unwrap();
+        spec::namespace_schemas(&mut spec, component);
+
+        if merged.is_null() {
+            merged = spec;
+            continue;
+        }
+
+        for pointer in ["/paths", "/components/schemas"] {
+            if let Some(serde_json::Value::Object(values)) =
+                spec.pointer_mut(pointer).map(serde_json::Value::take)
+            {
+                let target = pointer
+                    .split('/')
+                    .skip(1)
+                    .fold(&mut merged, |value, key| &mut value[key]);
+
+                match target {
+                    serde_json::Value::Object(target) => target.extend(values),
+                    target => *target = serde_json::Value::Object(values),
+                }
+            }
+        }
+        if let Some(serde_json::Value::Array(tags)) =
+            spec.get_mut("tags").map(serde_json::Value::take)
+        {
+            match &mut merged["tags"] {
+                serde_json::Value::Array(merged) => {
+                    for tag in tags {
+                        if !merged.iter().any(|other| other["name"] == tag["name"]) {
+                            merged.push(tag);
+                        }
+                    }
+                }
+                merged => *merged = serde_json::Value::Array(tags),
+            }
+        }
+    }
+
+    serde_json::from_value::<utoipa::openapi::OpenApi>(merged)
+        .expect("the merged declarations are valid")
+        .into()
+}
+
+/// Build the OpenAPI declaration of the endpoints of a component, without its information.
+fn declare(endpoints: &[Endpoint]) -> OpenApiBuilder {
+    let paths = endpoints
+        .iter()
+        .cloned()
+        .fold(PathsBuilder::new(), |paths, e| {
+            paths.path(e.path.clone(), e.into())
+        });
+    let mut schemas = BTreeMap::<String, (&Endpoint, TypeId)>::new();
+
+    for endpoint in endpoints {
+        let resolve = &endpoint.resolve;
+        let prototype = &endpoint.prototype;
+        let mut named = BTreeMap::new();
+
+        for ty in prototype.params.iter().map(|(_, ty)| ty) {
+            Type(*ty).collect_named(resolve, &mut named);
+        }
+        for ty in prototype.results.iter_types() {
+            Type(*ty).collect_named(resolve, &mut named);
+        }
+
+        for (name, id) in named {
+            schemas.entry(name).or_insert((endpoint, id));
+        }
+    }
+
+    // The tags in the order of their first endpoint
+    let mut tags = Vec::<Tag>::new();
+    for endpoint in endpoints {
+        if !tags.iter().any(|tag| tag.name == endpoint.tag.name) {
+            tags.push(endpoint.tag.clone());
+        }
+    }
+
+    let components = (!endpoints.is_empty()).then(|| {
+        schemas
+            .into_iter()
+            .fold(
+                ComponentsBuilder::new(),
+                |components, (name, (endpoint, id))| {
+                    components.schema(
+                        name,
+                        Type::definition(&endpoint.resolve, id, &*endpoint.mapper),
+                    )
+                },
+            )
+            .build()
+    });
+
+    OpenApiBuilder::new()
+        .paths(paths)
+        .components(components)
+        .tags((!tags.is_empty()).then_some(tags))
+}
+
+fn add_webhooks(spec: &mut serde_json::Value, endpoints: &[Endpoint]) {
+    webhooks::add_webhooks(
+        spec,
+        endpoints.iter().flat_map(|endpoint| {
+            let payload = serde_json::to_value(endpoint.result_schema()).unwrap();
+
+            endpoint
+                .webhooks
+                .iter()
+                .map(move |webhook| (webhook, payload.clone()))
+        }),
+    );
+}
+
+fn parse_address(s: &str) -> anyhow::Result<IpAddr> {
+    if s == "localhost" {
+        return Ok(IpAddr::V4(Ipv4Addr::LOCALHOST));
+    }
+
+    // The IPv6 addresses may be given as in URLs, e.g. `[::1]`
+    let ip = s
+        .strip_prefix('[')
+        .and_then(|s| s.strip_suffix(']'))
+        .unwrap_or(s);
+
+    ip.parse().map_err(|_| {
+        anyhow!("expected an IPv4 or IPv6 address, e.g. `127.0.0.1`, `0.0.0.0` or `::1`")
+    })
+}
+
+/// Bind the server's address before building it, to report the usual failures clearly.
+fn bind(address: IpAddr, port: u16) -> anyhow::Result<TcpListener> {
+    TcpListener::bind((address, port)).map_err(|e| {
+        let hint = match e.kind() {
+            ErrorKind::AddrInUse => "the port may already be used by another process",
+            ErrorKind::PermissionDenied => {
+                "binding this port may require elevated privileges, try a port above 1024"
+            }
+            ErrorKind::AddrNotAvailable => "the address may not belong to this host",
+            _ => "check the --address and --port options",
+        };
+
+        anyhow!(
+            "Failed to bind {}: {} ({})",
+            SocketAddr::new(address, port),
+            e,
+            hint
+        )
+    })
+}
+
+async fn openapi_spec(spec: web::Data<serde_json::Value>) -> HttpResponse {
+    HttpResponse::Ok().json(&**spec)
+}
+
+async fn openapi_yaml_spec(
+    request: HttpRequest,
+    spec: web::Data<serde_json::Value>,
+) -> HttpResponse {
+    match spec::serialize(&spec, spec::Format::Yaml, true) {
+        Ok(yaml) => HttpResponse::Ok()
+            .content_type("application/yaml")
+            .body(yaml),
+        Err(e) => ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e))
+            .respond_to(&request),
+    }
+}
+
+/// A base path, normalized to start with a `/` and not end with one (e.g. `/api/v2`).
+fn parse_base_path(s: &str) -> anyhow::Result<String> {
+    let path = s.trim_matches('/');
+    if path.is_empty() || path.contains(['{', '}', '?', '#']) {
+        bail!("expected a path such as `/api/v2`");
+    }
+
+    Ok(format!("/{}", path))
+}
+
+/// The `v<major>` path prefix of an API version.
+fn path_version_of(version: &str) -> String {
+    let major = version
+        .trim_start_matches('v')
+        .split('.')
+        .next()
+        .unwrap_or_default();
+
+    format!("v{}", major)
+}
+
+/// Describe a single endpoint with its OpenAPI operation object.
+async fn describe(
+    request: HttpRequest,
+    spec: web::Data<serde_json::Value>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let operation = spec
+        .get("paths")
+        .and_then(|paths| paths.get(format!("/{}", path)))
+        .and_then(serde_json::Value::as_object)
+        .and_then(|item| item.values().find(|operation| operation.is_object()));
+
+    match operation {
+        Some(operation) => HttpResponse::Ok().json(operation),
+        None => ApiError::new(StatusCode::NOT_FOUND, format!("no endpoint at `/{}`", path))
+            .respond_to(&request),
+    }
+}
+
+/// Generate the OpenAPI definition of a WASM component, as the `convert` command does with
+/// `--stub-imports --wasi` and otherwise the default options: the imports of the component are
+/// stubbed, and the WASI host functions linked without access to any directory nor environment
+/// variable, for it to be instantiated in isolation.
+pub fn component_to_openapi(bytes: &[u8]) -> anyhow::Result<OpenApi> {
+    component_to_openapi_with(bytes, Arc::new(()))
+}
+
+/// Generate the OpenAPI definition of a WASM component, with a custom mapping of its types to
+/// schemas.
+pub fn component_to_openapi_with(
+    bytes: &[u8],
+    mapper: Arc<dyn SchemaMapper>,
+) -> anyhow::Result<OpenApi> {
+    let wit = wit_component::decode(bytes).context("Failed to decode WIT component")?;
+    let mut functions = list_wasm_component_functions(&wit, None, None);
+    let openapi_info = functions
+        .iter()
+        .position(|exported| {
+            exported.instance.is_none() && exported.function.name == info::OPENAPI_INFO
+        })
+        .map(|i| functions.remove(i));
+
+    let engine = Engine::new(Config::new().wasm_component_model(true))?;
+    let component = Component::from_binary(&engine, bytes).context("Failed to load component")?;
+    let wasi = WasiOptions::sandboxed();
+    let mut linker: Linker<Host> = Linker::new(&engine);
+    stubs::stub_imports(
+        &mut linker,
+        &component,
+        wit.resolve(),
+        &ImportValues::default(),
+        true,
+        &defaults::<ValueOptions>(),
+    )?;
+    wasi.add_to_linker(&mut linker)?;
+    let pre = linker
+        .instantiate_pre(&component)
+        .context("Failed to instantiate component")?;
+    let pool = Arc::new(InstancePool::new(&engine, pre, wasi, 1, None, None));
+
+    let mut info_options = defaults::<InfoOptions>();
+    if openapi_info.is_some() {
+        let mut instance = pool.get()?;
+        let component_instance = instance.instance();
+
+        info_options.fill(instance.run(|store| info::call(&component_instance, store))?);
+    }
+
+    let endpoints = get_endpoints(
+        &Arc::new(wit.resolve().clone()),
+        functions,
+        &pool,
+        None,
+        &defaults(),
+        &Arc::new(defaults()),
+        &mapper,
+    )?;
+
+    Ok(openapi(&endpoints, &info_options).build())
+}
+
+/// The default values of command line options.
+fn defaults<T: Args + FromArgMatches>() -> T {
+    let matches = T::augment_args(clap::Command::new("defaults")).get_matches_from(["defaults"]);
+
+    T::from_arg_matches(&matches).expect("The options have default values")
+}
+
+/// Run the command line interface.
+pub async fn run() -> anyhow::Result<()> {
+    let mut stdout = std::io::stdout();
+    let mut stderr = std::io::stderr();
+
+    if let Some((server, _)) = start(Cli::parse(), &mut stdout, &mut stderr).await? {
+        server.await?;
+
+        log::info!("Server stopped");
+    }
+
+    Ok(())
+}
+
+/// The endpoints of the public spec, annotated with `@public`, and the endpoints served, only the
+/// public ones with `--public-only`.
+fn public_endpoints(endpoints: Vec<Endpoint>, public_only: bool) -> (Vec<Endpoint>, Vec<Endpoint>) {
+    let public = endpoints
+        .iter()
+        .filter(|endpoint| endpoint.public)
+        .cloned()
+        .collect::<Vec<_>>();
+    let served = match public_only {
+        true => public.clone(),
+        false => endpoints,
+    };
+
+    (public, served)
+}
+
+/// Run the command of the arguments, up to starting the server of the `serve` one, which is
+/// returned along with the address it's declared at rather than awaited.
+///
+/// The output of the commands is written to `stdout` and `stderr`, standing for the standard
+/// streams.
+async fn start(
+    mut args: Cli,
+    stdout: &mut impl Write,
+    stderr: &mut impl Write,
+) -> anyhow::Result<Option<(Server, SocketAddr)>> {
+    // Load the WASM components, named after their files when there are several
+    let mut components: Vec<(Option<String>, Vec<u8>, DecodedWasm)> = vec![];
+    for file in &args.file {
+        let name = match args.file.len() {
+            1 => None,
+            _ => Some(file.name()?),
+        };
+
+        if let Some(name) = name.as_ref().filter(|name| {
+            components
+                .iter()
+                .any(|(other, _, _)| other.as_ref() == Some(*name))
+        }) {
+            bail!(
+                "Several components are named `{}`, rename their files to serve them under distinct prefixes",
+                name
+            );
+        }
+
+        let data = file.load(&args.fetch_options).await?;
+        // Decode the component's WIT
+        let wit = wit_component::decode(&data)
+            .with_context(|| format!("Failed to decode the component {}", file))?;
+
+        components.push((name, data, wit));
+    }
+
+    let mut functions = components
+        .iter()
+        .zip(&args.file)
+        .map(|((name, _, wit), file)| {
+            list_wasm_component_functions(wit, name.clone(), file.name().ok())
+        })
+        .collect::<Vec<_>>();
+    // The functions describing the API aren't endpoints of it
+    let openapi_info = functions
+        .iter_mut()
+        .map(|functions| {
+            functions
+                .iter()
+                .position(|exported| {
+                    exported.instance.is_none() && exported.function.name == info::OPENAPI_INFO
+                })
+                .map(|i| functions.remove(i))
+        })
+        .collect::<Vec<_>>();
+    let total = functions.iter().map(Vec::len).sum::<usize>();
+
+    if let Some(max) = args.max_endpoints.filter(|max| total > *max) {
+        match args.max_endpoints_policy {
+            MaxEndpointsPolicy::Error => bail!(
+                "The component exports {} functions, more than the maximum of {} endpoints",
+                total,
+                max
+            ),
+            MaxEndpointsPolicy::Truncate => {
+                log::warn!(
+                    "The component exports {} functions, only the first {} are exposed",
+                    total,
+                    max
+                );
+
+                let mut remaining = max;
+                for functions in &mut functions {
+                    functions.truncate(remaining);
+                    remaining -= functions.len();
+                }
+            }
+        }
+    }
+
+    if let Command::Coverage = args.command {
+        let functions = components
+            .iter()
+            .zip(&functions)
+            .map(|((_, _, wit), functions)| (wit.resolve(), functions.as_slice()))
+            .collect::<Vec<_>>();
+        let report = coverage::report(&functions, &args.path_options);
+
+        writeln!(stdout, "{}", serde_json::to_string(&report).unwrap())?;
+
+        return Ok(None);
+    }
+
+    // Instantiate the WASM components on demand, each instance in its own store
+    let config = {
+        let mut config = Config::new();
+        config.wasm_component_model(true);
+        config.consume_fuel(args.max_fuel.is_some());
+        config.epoch_interruption(args.call_timeout.is_some());
+        config
+    };
+    let engine = Engine::new(&config).expect("Failed to create WASM engine");
+    let call_timeout = args.call_timeout.map(Duration::from_millis);
+    if call_timeout.is_some() {
+        timeout::start_ticker(&engine);
+    }
+    let options = Arc::new(args.value_options);
+    let mapper = options.mapper(args.spec_options.openapi_version());
+    let import_values = match &args.import_values {
+        Some(path) => ImportValues::load(path)?,
+        None => ImportValues::default(),
+    };
+    let max_instances = args.max_instances.unwrap_or_else(|| {
+        std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get)
+    });
+    let mut endpoints = vec![];
+
+    for (((_, data, wit), file), (functions, openapi_info)) in components
+        .iter()
+        .zip(&args.file)
+        .zip(functions.into_iter().zip(openapi_info))
+    {
+        let component = cache::load(&engine, data, file, !args.no_cache)?;
+        let mut linker: Linker<Host> = Linker::new(&engine);
+        stubs::stub_imports(
+            &mut linker,
+            &component,
+            wit.resolve(),
+            &import_values,
+            args.stub_imports,
+            &options,
+        )?;
+        args.wasi_options.add_to_linker(&mut linker)?;
+        let pre = linker
+            .instantiate_pre(&component)
+            .context("Failed to instantiate component")?;
+        let pool = Arc::new(InstancePool::new(
+            &engine,
+            pre,
+            args.wasi_options.clone(),
+            max_instances,
+            args.max_fuel,
+            call_timeout,
+        ));
+
+        // The first component describing the API describes the whole of it
+        if openapi_info.is_some() {
+            let mut instance = pool.get()?;
+            let component_instance = instance.instance();
+            let info = instance.run(|store| info::call(&component_instance, store))?;
+
+            args.info_options.fill(info);
+        }
+
+        endpoints.extend(get_endpoints(
+            &Arc::new(wit.resolve().clone()),
+            functions,
+            &pool,
+            args.max_fuel,
+            &args.path_options,
+            &options,
+            &mapper,
+        )?);
+    }
+
+    match args.command {
+        Command::Coverage => unreachable!(),
+        Command::Convert {
+            output_stream,
+            format,
+            pretty,
+            server_url,
+            emit_schemas,
+        } => {
+            let openapi = openapi(&endpoints, &args.info_options);
+            let openapi = match server_url.is_empty() {
+                true => openapi,
+                false => openapi.servers(Some(
+                    server_url
+                        .iter()
+                        .map(|url| ServerBuilder::new().url(url).build())
+                        .collect::<Vec<_>>(),
+                )),
+            };
+            let mut spec = serde_json::to_value(openapi.build()).unwrap();
+            add_webhooks(&mut spec, &endpoints);
+            if let Some(dir) = &emit_schemas {
+                schemas::emit(&spec::with_schemas(&spec, &args.spec_options), dir)?;
+            }
+            spec::postprocess(&mut spec, &args.spec_options)?;
+            if format == spec::Format::Postman {
+                let examples = Examples::new(&spec, args.spec_options.option_examples());
+
+                spec = postman::collection(&spec, &endpoints, &examples);
+            }
+
+            let output = spec::serialize(&spec, format, pretty)?;
+
+            let stream: &mut dyn Write = match output_stream {
+                OutputStream::Stdout => stdout,
+                OutputStream::Stderr => stderr,
+            };
+            writeln!(stream, "{}", output)?;
+        }
+        Command::Validate => {
+            let mut invalid = 0;
+
+            for endpoint in &endpoints {
+                let prototype = &endpoint.prototype;
+                let params = prototype
+                    .params
+                    .iter()
+                    .map(|(name, ty)| (format!("parameter `{}`", name), ty));
+                let results = match &prototype.results {
+                    wit_parser::Results::Named(results) => results
+                        .iter()
+                        .map(|(name, ty)| (format!("result `{}`", name), ty))
+                        .collect::<Vec<_>>(),
+                    wit_parser::Results::Anon(ty) => vec![(String::from("result"), ty)],
+                };
+                let unsupported = params
+                    .chain(results)
+                    .filter_map(|(what, ty)| {
+                        Type(*ty)
+                            .unsupported(&endpoint.resolve)
+                            .map(|kind| format!("{} of unsupported type `{}`", what, kind))
+                    })
+                    .collect::<Vec<_>>();
+
+                if !unsupported.is_empty() {
+                    invalid += 1;
+                    writeln!(stdout, "{}: {}", endpoint.path, unsupported.join(", "))?;
+                }
+            }
+
+            if invalid > 0 {
+                bail!(
+                    "{} of the {} endpoints use types that are not supported yet",
+                    invalid,
+                    endpoints.len()
+                );
+            }
+
+            writeln!(stdout, "The {} endpoints can be served", endpoints.len())?;
+        }
+        Command::Repl => repl::run(&endpoints)?,
+        Command::ValidateRequest { path, body } => {
+            let mut spec =
+                serde_json::to_value(openapi(&endpoints, &args.info_options).build()).unwrap();
+            spec::postprocess(&mut spec, &args.spec_options)?;
+
+            let path = format!("/{}", path.trim_start_matches('/'));
+            let schema = spec
+                .get("paths")
+                .and_then(|paths| paths.get(&path))
+                .and_then(validation::request_schema)
+                .ok_or_else(|| anyhow!("No `{}` endpoint", path))?;
+            let body = match &body {
+                Some(file) => std::fs::read_to_string(file)
+                    .with_context(|| format!("Failed to read {}", file.display()))?,
+                None => std::io::read_to_string(std::io::stdin())
+                    .context("Failed to read the standard input")?,
+            };
+            let body = serde_json::from_str(&body).context("The request body isn't valid JSON")?;
+            let errors = validation::errors(&validation::validator(&schema, &spec)?, &body);
+
+            for error in &errors {
+                writeln!(stderr, "{}", error)?;
+            }
+            if !errors.is_empty() {
+                bail!(
+                    "The request body doesn't validate against the `{}` request schema",
+                    path
+                );
+            }
+        }
+        Command::Serve {
+            swagger,
+            swagger_deep_linking,
+            swagger_default_try_it_out,
+            swagger_doc_expansion,
+            address,
+            all_interfaces,
+            port,
+            jwt_jwks_url,
+            jwt_audience,
+            jwt_issuer,
+            auth_token,
+            response_header,
+            base_path,
+            path_version,
+            public_only,
+            trusted_proxy,
+            log_format,
+            verbose_errors,
+            jsonrpc,
+            rpc_methods,
+            rpc_method_map,
+            max_body_size,
+            metrics,
+            no_health,
+            shutdown_timeout,
+            cors,
+            tls,
+        } => {
+            let tls = tls.config()?;
+            let address = match all_interfaces {
+                true => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+                false => address,
+            };
+            let listener = bind(address, port)?;
+            // The server is declared at the bound address, with the port picked by the system for
+            // `--port 0`, and at the loopback one when bound to every interface
+            let server_address = {
+                let mut server_address = listener.local_addr()?;
+                if server_address.ip().is_unspecified() {
+                    server_address.set_ip(match server_address.ip() {
+                        IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+                        IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+                    });
+                }
+                server_address
+            };
+            let scheme = match tls {
+                Some(_) => "https",
+                None => "http",
+            };
+            let jwt = match jwt_jwks_url {
+                Some(url) => Some(Arc::new(
+                    JwtValidator::fetch(&url, jwt_audience, jwt_issuer).await?,
+                )),
+                None => None,
+            };
+            let version = match path_version.as_deref() {
+                None => String::new(),
+                Some("") => format!(
+                    "/{}",
+                    path_version_of(&openapi(&endpoints, &args.info_options).build().info.version)
+                ),
+                Some(version) => format!("/{}", version.trim_matches('/')),
+            };
+            let prefix = format!("{}{}", base_path.unwrap_or_default(), version);
+            let (public_endpoints, endpoints) = public_endpoints(endpoints, public_only);
+            let spec_of = |endpoints: &[Endpoint]| {
+                let mut openapi = openapi(endpoints, &args.info_options)
+                    .servers(Some(vec![ServerBuilder::new()
+                        .url(format!("{}://{}{}", scheme, server_address, prefix))
+                        .build()]))
+                    .build();
+                if auth_token.is_some() {
+                    openapi
+                        .components
+                        .get_or_insert_with(Default::default)
+                        .add_security_scheme(
+                            BEARER_SCHEME,
+                            SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+                        );
+                    openapi.security = Some(vec![SecurityRequirement::new(
+                        BEARER_SCHEME,
+                        Vec::<String>::new(),
+                    )]);
+                }
+                let mut spec = serde_json::to_value(openapi).unwrap();
+                add_webhooks(&mut spec, endpoints);
+                spec::postprocess(&mut spec, &args.spec_options)?;
+
+                anyhow::Ok(spec)
+            };
+            let health_probe = endpoints.iter().find(|endpoint| {
+                let path = format!("{}{}", prefix, endpoint.path);
+
+                path == health::HEALTHZ || path == health::READYZ
+            });
+            if let Some(endpoint) = health_probe.filter(|_| !no_health) {
+                bail!(
+                    "The `{}` endpoint collides with a health probe, serve it with --no-health",
+                    endpoint.path
+                );
+            }
+            if let Some(endpoint) = endpoints
+                .iter()
+                .find(|endpoint| metrics && format!("{}{}", prefix, endpoint.path) == METRICS)
+            {
+                bail!(
+                    "The `{}` endpoint collides with the metrics endpoint",
+                    endpoint.path
+                );
+            }
+            if let Some(endpoint) = endpoints
+                .iter()
+                .find(|endpoint| jsonrpc && endpoint.path == jsonrpc::RPC)
+            {
+                bail!(
+                    "The `{}` endpoint collides with the JSON-RPC endpoint",
+                    endpoint.path
+                );
+            }
+
+            let spec = web::Data::new(spec_of(&endpoints)?);
+            // The spec of each component on its own, when serving several ones
+            let mut component_specs = HashMap::new();
+            for component in endpoints.iter().filter_map(|e| e.component.as_ref()) {
+                if !component_specs.contains_key(component) {
+                    let endpoints = endpoints
+                        .iter()
+                        .filter(|endpoint| endpoint.component.as_ref() == Some(component))
+                        .cloned()
+                        .collect::<Vec<_>>();
+
+                    component_specs.insert(component.clone(), spec_of(&endpoints)?);
+                }
+            }
+            let component_specs = Arc::new(component_specs);
+            let rpc_methods = Arc::new(match &rpc_method_map {
+                Some(path) => Methods::load(path)?,
+                None => Methods::Names(rpc_methods),
+            });
+            rpc_methods.check(&endpoints)?;
+            let rpc_endpoints = Arc::new(endpoints.clone());
+            let call_metrics = web::Data::new(Metrics::default());
+            let public_spec = Arc::new(spec_of(&public_endpoints)?);
+
+            let server = HttpServer::new(move || {
+                let app = App::new()
+                    .app_data(spec.clone())
+                    .app_data(
+                        web::JsonConfig::default()
+                            .limit(max_body_size)
+                            .error_handler(|err, request| {
+                                let response = body::payload_error(&err).respond_to(request);
+
+                                InternalError::from_response(err, response).into()
+                            }),
+                    )
+                    .app_data(web::PayloadConfig::new(max_body_size))
+                    .route(
+                        &format!("{}/__describe/{{path:.*}}", prefix),
+                        web::get().to(describe),
+                    )
+                    .wrap(
+                        response_header
+                            .iter()
+                            .cloned()
+                            .fold(DefaultHeaders::new(), DefaultHeaders::add),
+                    )
+                    .wrap(Condition::new(cors.enabled(), cors.middleware()))
+                    .wrap(proxy::access_log(&trusted_proxy, log_format));
+                let app = app
+                    .route(
+                        &format!("{}/openapi.json", prefix),
+                        web::get().to(openapi_spec),
+                    )
+                    .route(
+                        &format!("{}/openapi.yaml", prefix),
+                        web::get().to(openapi_yaml_spec),
+                    )
+                    .route(
+                        &format!("{}/openapi.public.json", prefix),
+                        web::get().to({
+                            let public_spec = public_spec.clone();
+
+                            move || {
+                                let public_spec = public_spec.clone();
+
+                                async move { HttpResponse::Ok().json(&*public_spec) }
+                            }
+                        }),
+                    )
+                    .route(
+                        &format!("{}/components/{{name}}/openapi.json", prefix),
+                        web::get().to({
+                            let component_specs = component_specs.clone();
+
+                            move |request: HttpRequest, name: web::Path<String>| {
+                                let component_specs = component_specs.clone();
+
+                                async move {
+                                    match component_specs.get(name.as_str()) {
+                                        Some(spec) => HttpResponse::Ok().json(spec),
+                                        None => ApiError::new(
+                                            StatusCode::NOT_FOUND,
+                                            format!("no component named `{}`", name),
+                                        )
+                                        .respond_to(&request),
+                                    }
+                                }
+                            }
+                        }),
+                    );
+                let app = match no_health {
+                    true => app,
+                    false => app
+                        .route(health::HEALTHZ, web::get().to(health::healthz))
+                        .route(health::READYZ, web::get().to(health::readyz)),
+                };
+                let app = match metrics {
+                    false => app,
+                    true => app
+                        .app_data(call_metrics.clone())
+                        .route(METRICS, web::get().to(metrics::metrics)),
+                };
+                let app = match jsonrpc {
+                    false => app,
+                    true => {
+                        let endpoints = rpc_endpoints.clone();
+                        let methods = rpc_methods.clone();
+                        let jwt = jwt.clone();
+                        let auth = BearerAuth::new(auth_token.as_deref().unwrap_or_default());
+
+                        app.route(
+                            &format!("{}{}", prefix, jsonrpc::RPC),
+                            web::post()
+                                .to(move |request: HttpRequest, payload: web::Payload| {
+                                    let endpoints = endpoints.clone();
+                                    let methods = methods.clone();
+                                    let jwt = jwt.clone();
+
+                                    async move {
+                                        // Authorized before the body is read, so that the
+                                        // unauthorized requests can't be told from their body
+                                        if let Err(e) = match &jwt {
+                                            Some(jwt) => jwt.authorize(&request),
+                                            None => Ok(()),
+                                        } {
+                                            return e.respond_to(&request);
+                                        }
+                                        let body = match web::Bytes::from_request(
+                                            &request,
+                                            &mut payload.into_inner(),
+                                        )
+                                        .await
+                                        {
+                                            Ok(body) => body,
+                                            Err(e) => {
+                                                let error =
+                                                    match e.as_response_error().status_code() {
+                                                        StatusCode::PAYLOAD_TOO_LARGE => {
+                                                            body::too_large(max_body_size)
+                                                        }
+                                                        _ => ApiError::bad_request(e),
+                                                    };
+
+                                                return error.respond_to(&request);
+                                            }
+                                        };
+
+                                        jsonrpc::handle(
+                                            &endpoints,
+                                            &methods,
+                                            &request,
+                                            &body,
+                                            verbose_errors,
+                                        )
+                                        .await
+                                    }
+                                })
+                                .wrap(Condition::new(auth_token.is_some(), auth)),
+                        )
+                    }
+                };
+                let app = if swagger {
+                    let url = format!("{}/api-docs/openapi.json", prefix);
+                    let mut config = SwaggerConfig::new([url.clone()]);
+                    if let Some(deep_linking) = swagger_deep_linking {
+                        config = config.deep_linking(deep_linking);
+                    }
+                    if let Some(try_it_out) = swagger_default_try_it_out {
+                        config = config.try_it_out_enabled(try_it_out);
+                    }
+                    if let Some(expansion) = swagger_doc_expansion {
+                        config = config.doc_expansion(expansion.as_str());
+                    }
+
+                    app.service(
+                        SwaggerUi::new(format!("{}/swagger-ui/{{_:.*}}", prefix))
+                            .external_url_unchecked(url, (**spec).clone())
+                            .config(config),
+                    )
+                } else {
+                    app
+                };
+
+                endpoints.clone().into_iter().fold(app, |app, endpoint| {
+                    let jwt = jwt.clone();
+                    let auth = BearerAuth::new(auth_token.as_deref().unwrap_or_default());
+                    let route = match endpoint.method {
+                        PathItemType::Get => web::get(),
+                        _ => web::post(),
+                    };
+
+                    app.route(
+                        &format!("{}{}", prefix, endpoint.path),
+                        route
+                            .to(move |request: HttpRequest, payload: web::Payload| {
+                                let endpoint = endpoint.clone();
+                                let jwt = jwt.clone();
+
+                                async move {
+                                    // Authorized before the query or the body is parsed, so that the
+                                    // unauthorized requests get a 401 rather than a 400 or a 413
+                                    if let Err(e) = match &jwt {
+                                        Some(jwt) => jwt.authorize(&request),
+                                        None => Ok(()),
+                                    } {
+                                        return e.respond_to(&request);
+                                    }
+
+                                    let payload = match endpoint.method {
+                                        PathItemType::Get => {
+                                            match endpoint.query_parameters(&request) {
+                                                Ok(payload) => Some(payload),
+                                                Err(e) => return e.respond_to(&request),
+                                            }
+                                        }
+                                        _ => match body::read(&request, &mut payload.into_inner())
+                                            .await
+                                        {
+                                            Ok(payload) => payload,
+                                            Err(e) => return e.error_response(),
+                                        },
+                                    };
+
+                                    endpoint
+                                        .call(&request, payload, verbose_errors)
+                                        .await
+                                        .unwrap_or_else(|e| e.respond_to(&request))
+                                }
+                            })
+                            .wrap(Condition::new(auth_token.is_some(), auth)),
+                    )
+                })
+            })
+            .shutdown_timeout(shutdown_timeout)
+            .disable_signals();
+            let server = match tls {
+                Some(config) => server.listen_rustls_0_21(listener, config)?,
+                None => server.listen(listener)?,
+            }
+            .run();
+
+            actix_web::rt::spawn(shutdown::on_signal(
+                server.handle(),
+                Duration::from_secs(shutdown_timeout),
+            ));
+
+            return Ok(Some((server, server_address)));
+        }
+    };
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use wasmtime::component::Type;
+
+    use super::*;
+
+    const VALUES_WIT: &str = r#"
+        package test:values;
+
+        interface types {
+            enum color { red, green, blue }
+            record point { x: s32, y: s32 }
+            variant shape { circle(u32), empty }
+            flags permissions { read, write }
+        }
+
+        world values {
+            use types.{color, point, shape, permissions};
+
+            export f: func(
+                color: color,
+                point: point,
+                shape: shape,
+                permissions: permissions,
+                numbers: list<u32>,
+                bytes: list<u8>,
+                pair: tuple<u32, string>,
+                maybe: option<u32>,
+                checked: result<u32, string>,
+            );
+        }
+    "#;
+
+    /// Decode each JSON value, expecting either its JSON value once decoded or an error message.
+    fn check(
+        ty: &Type,
+        options: &ValueOptions,
+        cases: &[(serde_json::Value, Result<serde_json::Value, &str>)],
+    ) {
+        for (input, expected) in cases {
+            let decoded = Value::from_json(input.clone(), ty, options)
+                .map(|value| value.to_json(options))
+                .map_err(|e| e.to_string());
+
+            assert_eq!(
+                decoded,
+                expected.clone().map_err(str::to_string),
+                "decoding {} as {:?}",
+                input,
+                ty
+            );
+        }
+    }
+
+    #[test]
+    fn decodes_the_primitive_values() {
+        let options = defaults::<ValueOptions>();
+        let same = |v: serde_json::Value| (v.clone(), Ok(v));
+
+        check(
+            &Type::Bool,
+            &options,
+            &[
+                same(json!(true)),
+                (json!(1), Err("expected a boolean, got a number")),
+            ],
+        );
+        check(
+            &Type::U8,
+            &options,
+            &[
+                same(json!(0)),
+                same(json!(255)),
+                (json!(256), Err("expected u8 0..=255, got 256")),
+                (json!(-1), Err("expected u8 0..=255, got -1")),
+                (json!(1.5), Err("expected an integer, got 1.5")),
+                (json!("1"), Err("expected an integer, got a string")),
+            ],
+        );
+        check(
+            &Type::S8,
+            &options,
+            &[
+                same(json!(-128)),
+                same(json!(127)),
+                (json!(128), Err("expected s8 -128..=127, got 128")),
+            ],
+        );
+        check(
+            &Type::U16,
+            &options,
+            &[
+                same(json!(65535)),
+                (json!(65536), Err("expected u16 0..=65535, got 65536")),
+            ],
+        );
+        check(
+            &Type::S16,
+            &options,
+            &[
+                same(json!(-32768)),
+                (
+                    json!(-32769),
+                    Err("expected s16 -32768..=32767, got -32769"),
+                ),
+            ],
+        );
+        check(
+            &Type::U32,
+            &options,
+            &[
+                same(json!(4294967295u32)),
+                (json!(-1), Err("expected u32 0..=4294967295, got -1")),
+            ],
+        );
+        check(
+            &Type::S32,
+            &options,
+            &[
+                same(json!(i32::MIN)),
+                (
+                    json!(2147483648u32),
+                    Err("expected s32 -2147483648..=2147483647, got 2147483648"),
+                ),
+            ],
+        );
+        check(
+            &Type::U64,
+            &options,
+            &[
+                same(json!(u64::MAX)),
+                (
+                    json!(-1),
+                    Err("expected u64 0..=18446744073709551615, got -1"),
+                ),
+                (json!("1"), Err("expected an integer, got a string")),
+            ],
+        );
+        check(
+            &Type::S64,
+            &options,
+            &[
+                same(json!(i64::MIN)),
+                (
+                    json!(u64::MAX),
+                    Err("expected s64 -9223372036854775808..=9223372036854775807, got 18446744073709551615"),
+                ),
+            ],
+        );
+        check(
+            &Type::Float64,
+            &options,
+            &[
+                same(json!(1.5)),
+                same(json!(-0.25)),
+                (json!("1.5"), Err("expected a number, got a string")),
+            ],
+        );
+        check(
+            &Type::Float32,
+            &options,
+            &[
+                same(json!(0.5)),
+                (json!(null), Err("expected a number, got null")),
+            ],
+        );
+        check(
+            &Type::String,
+            &options,
+            &[
+                same(json!("")),
+                same(json!("λ")),
+                (json!(1), Err("expected a string, got a number")),
+            ],
+        );
+        check(
+            &Type::Char,
+            &options,
+            &[
+                same(json!("λ")),
+                same(json!("🦀")),
+                (json!(""), Err("expected a character, got an empty string")),
+                (
+                    json!("ab"),
+                    Err("expected a single character, got 2 characters"),
+                ),
+                (json!(1), Err("expected a character, got a number")),
+            ],
+        );
+    }
+
+    #[test]
+    fn decodes_the_numbers_given_as_strings() {
+        let mut options = defaults::<ValueOptions>();
+        options.coerce_strings = true;
+        options.non_finite = NonFinitePolicy::String;
+
+        check(
+            &Type::Float64,
+            &options,
+            &[
+                (json!(" 1.5 "), Ok(json!(1.5))),
+                (json!("inf"), Ok(json!("Infinity"))),
+                (json!("-Infinity"), Ok(json!("-Infinity"))),
+                (json!("1e400"), Err("`1e400` is out of range")),
+                (json!("x"), Err("`x` is not a valid number")),
+            ],
+        );
+
+        let mut options = defaults::<ValueOptions>();
+        options.bigint_as_string = true;
+
+        check(
+            &Type::U64,
+            &options,
+            &[
+                (
+                    json!("18446744073709551615"),
+                    Ok(json!("18446744073709551615")),
+                ),
+                (json!(3), Ok(json!("3"))),
+                (
+                    json!("18446744073709551616"),
+                    Err("expected u64 0..=18446744073709551615, got 18446744073709551616"),
+                ),
+                (json!("x"), Err("expected an integer, got the string \"x\"")),
+            ],
+        );
+        check(&Type::S64, &options, &[(json!("-5"), Ok(json!("-5")))]);
+    }
+
+    #[test]
+    fn decodes_the_exact_float32_values() {
+        let mut options = defaults::<ValueOptions>();
+        options.strict_floats = true;
+        options.coerce_strings = true;
+        options.non_finite = NonFinitePolicy::String;
+
+        check(
+            &Type::Float32,
+            &options,
+            &[
+                (json!(0.5), Ok(json!(0.5))),
+                (json!(16777216), Ok(json!(16777216.0))),
+                (json!("nan"), Ok(json!("NaN"))),
+                (json!("-inf"), Ok(json!("-Infinity"))),
+                (
+                    json!(0.1),
+                    Err("0.1 can't be represented exactly as a float32"),
+                ),
+                (
+                    json!(16777217),
+                    Err("16777217 can't be represented exactly as a float32"),
+                ),
+                (json!(1e39), Err("1e39 is out of range for a float32")),
+            ],
+        );
+
+        options.strict_floats = false;
+        check(
+            &Type::Float32,
+            &options,
+            &[
+                (json!(0.1), Ok(json!(0.1f32 as f64))),
+                (json!(1e39), Ok(json!("Infinity"))),
+            ],
+        );
+    }
+
+    #[test]
+    fn decodes_the_compound_values() {
+        let types = testing::param_types(VALUES_WIT, "f");
+        let options = defaults::<ValueOptions>();
+        let same = |v: serde_json::Value| (v.clone(), Ok(v));
+
+        // color
+        check(
+            &types[0],
+            &options,
+            &[
+                same(json!("green")),
+                (json!("purple"), Err("unknown case `purple`")),
+                (json!(1), Err("expected a case name, got a number")),
+            ],
+        );
+        // point
+        check(
+            &types[1],
+            &options,
+            &[
+                same(json!({ "x": 1, "y": -2 })),
+                (json!({ "x": 1 }), Err("missing field `y`")),
+                (
+                    json!({ "x": 1, "y": "2" }),
+                    Err("field `y`: expected an integer, got a string"),
+                ),
+                (json!([1, 2]), Err("expected an object, got an array")),
+            ],
+        );
+        // shape
+        check(
+            &types[2],
+            &options,
+            &[
+                same(json!({ "tag": "circle", "value": 3 })),
+                same(json!({ "tag": "empty" })),
+                (json!({ "tag": "square" }), Err("unknown case `square`")),
+                (
+                    json!({ "tag": "circle" }),
+                    Err("case `circle`: missing `value`"),
+                ),
+                (
+                    json!({ "tag": "circle", "value": -1 }),
+                    Err("case `circle`: expected u32 0..=4294967295, got -1"),
+                ),
+                (json!({ "value": 3 }), Err("expected a case name as `tag`")),
+            ],
+        );
+        // permissions
+        check(
+            &types[3],
+            &options,
+            &[
+                same(json!([])),
+                same(json!(["read", "write"])),
+                (json!(["exec"]), Err("unknown flag `exec`")),
+                (json!([1]), Err("expected a flag name, got a number")),
+                (
+                    json!("read"),
+                    Err("expected an array of flag names, got a string"),
+                ),
+            ],
+        );
+        // numbers
+        check(
+            &types[4],
+            &options,
+            &[
+                same(json!([])),
+                same(json!([1, 2])),
+                (
+                    json!([1, "2"]),
+                    Err("element 1: expected an integer, got a string"),
+                ),
+                (json!({}), Err("expected an array, got an object")),
+            ],
+        );
+        // bytes
+        check(
+            &types[5],
+            &options,
+            &[
+                same(json!([0, 255])),
+                (json!([256]), Err("element 0: expected a byte")),
+                (json!([0, -1]), Err("element 1: expected a byte")),
+            ],
+        );
+        // pair
+        check(
+            &types[6],
+            &options,
+            &[
+                same(json!([1, "one"])),
+                (json!([1]), Err("expected 2 elements, got 1")),
+                (
+                    json!(["1", "one"]),
+                    Err("element 0: expected an integer, got a string"),
+                ),
+            ],
+        );
+        // maybe
+        check(
+            &types[7],
+            &options,
+            &[
+                same(json!(null)),
+                same(json!(3)),
+                (json!("3"), Err("expected an integer, got a string")),
+            ],
+        );
+        // checked
+        check(
+            &types[8],
+            &options,
+            &[
+                same(json!({ "ok": 1 })),
+                same(json!({ "err": "no" })),
+                (
+                    json!({ "ok": 1, "err": "no" }),
+                    Err("expected either `ok` or `err`"),
+                ),
+                (json!({}), Err("expected either `ok` or `err`")),
+                (
+                    json!({ "err": 1 }),
+                    Err("`err`: expected a string, got a number"),
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn decodes_the_enums_by_index() {
+        let types = testing::param_types(VALUES_WIT, "f");
+        let mut options = defaults::<ValueOptions>();
+
+        options.enum_as = EnumAs::Int;
+        check(
+            &types[0],
+            &options,
+            &[
+                (json!(2), Ok(json!(2))),
+                (json!(3), Err("3 is not a valid case index")),
+                (json!("blue"), Err("expected a case index, got a string")),
+            ],
+        );
+
+        options.enum_as = EnumAs::Both;
+        check(
+            &types[0],
+            &options,
+            &[
+                (json!(2), Ok(json!("blue"))),
+                (json!("red"), Ok(json!("red"))),
+                (
+                    json!(true),
+                    Err("expected a case name or index, got a boolean"),
+                ),
+            ],
+        );
+    }
+
+    #[actix_web::test]
+    async fn names_the_missing_parameters() {
+        let endpoints = testing::endpoints(
+            r#"
+                package test:add;
+
+                world add {
+                    export add: func(x: s32, y: s32) -> s32;
+                }
+            "#,
+            defaults(),
+        );
+        let request = actix_web::test::TestRequest::post().to_http_request();
+        let body = serde_json::from_str::<Body>("{}").unwrap();
+
+        let error = endpoints[0]
+            .call(&request, Some(body), false)
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.status, StatusCode::BAD_REQUEST);
+        assert_eq!(error.message, "missing parameters `x`, `y`");
+        assert_eq!(error.details["parameters"], json!(["x", "y"]));
+    }
+
+    #[test]
+    fn checks_the_signature_against_the_wit_declaration() {
+        let declared = testing::component(
+            r#"
+                package test:add;
+
+                world add {
+                    export add: func(x: string, y: s32) -> s32;
+                }
+            "#,
+        );
+        let declared = wit_component::decode(&declared).unwrap();
+        let functions = list_wasm_component_functions(&declared, None, None);
+        let pool = testing::pool(&testing::component(
+            r#"
+                package test:add;
+
+                world add {
+                    export add: func(x: s32, y: s32) -> s32;
+                }
+            "#,
+        ));
+        let mut instance = pool.get().unwrap();
+        let callable = instance.func(None, "add").unwrap();
+
+        let error = check_signature(
+            declared.resolve(),
+            functions[0].function,
+            &callable,
+            &*instance,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "parameter `x` is a s32, but a string in its WIT declaration"
+        );
+    }
+
+    #[actix_web::test]
+    async fn serves_the_internal_functions_out_of_the_public_spec() {
+        let wit = r#"
+            package test:calc;
+
+            world calc {
+                /// @public
+                export add: func(x: s32, y: s32) -> s32;
+                /// @internal
+                export sub: func(x: s32, y: s32) -> s32;
+            }
+        "#;
+        let component = testing::component_with(
+            wit,
+            r#"
+                (module
+                    (func (export "add") (param i32 i32) (result i32)
+                        (i32.add (local.get 0) (local.get 1)))
+                    (func (export "sub") (param i32 i32) (result i32)
+                        (i32.sub (local.get 0) (local.get 1))))
+            "#,
+        );
+        let endpoints = testing::endpoints_of(&component, wit, defaults());
+        let paths = |endpoints: &[Endpoint]| {
+            openapi(endpoints, &defaults())
+                .build()
+                .paths
+                .paths
+                .into_keys()
+                .collect::<Vec<_>>()
+        };
+
+        let (public, served) = public_endpoints(endpoints.clone(), false);
+        assert_eq!(paths(&public), ["/add"]);
+        assert_eq!(paths(&served), ["/add", "/sub"]);
+
+        let request = actix_web::test::TestRequest::post().to_http_request();
+        let body = serde_json::from_str::<Body>(r#"{"x": 3, "y": 2}"#).unwrap();
+        let response = served[1].call(&request, Some(body), false).await.unwrap();
+        let result = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        assert_eq!(result, "1");
+
+        let (_, served) = public_endpoints(endpoints, true);
+        assert_eq!(paths(&served), ["/add"]);
+    }
+
+    #[actix_web::test]
+    async fn rejects_more_functions_than_the_maximum_of_endpoints() {
+        let file = testing::TempFile::new(
+            "wasm",
+            testing::component(
+                r#"
+                    package test:calc;
+
+                    world calc {
+                        export add: func(x: s32, y: s32) -> s32;
+                        export sub: func(x: s32, y: s32) -> s32;
+                    }
+                "#,
+            ),
+        );
+        let args = Cli::try_parse_from([
+            "wasm2openapi",
+            "--file",
+            file.path(),
+            "--no-cache",
+            "--max-endpoints",
+            "1",
+            "convert",
+        ])
+        .unwrap();
+
+        let Err(error) = start(args, &mut vec![], &mut vec![]).await else {
+            panic!("the component was loaded");
+        };
+
+        assert_eq!(
+            error.to_string(),
+            "The component exports 2 functions, more than the maximum of 1 endpoints"
+        );
+    }
+
+    #[test]
+    fn constrains_the_enum_schemas_to_their_cases() {
+        let endpoints = testing::endpoints(
+            r#"
+                package test:paint;
+
+                interface types {
+                    enum color { red, green, blue }
+                }
+
+                world paint {
+                    use types.{color};
+
+                    export paint: func(color: color);
+                }
+            "#,
+            defaults(),
+        );
+
+        let spec = serde_json::to_value(openapi(&endpoints, &defaults()).build()).unwrap();
+
+        assert_eq!(
+            spec["components"]["schemas"]["color"],
+            json!({ "type": "string", "enum": ["red", "green", "blue"] })
+        );
+        assert_eq!(
+            spec["paths"]["/paint"]["post"]["requestBody"]["content"]["application/json"]["schema"]
+                ["oneOf"][0]["properties"]["color"],
+            json!({ "$ref": "#/components/schemas/color" })
+        );
+    }
+
+    #[test]
+    fn cases_the_paths() {
+        let component = testing::component(
+            r#"
+                package test:calc;
+
+                interface math-ops {
+                    add-numbers: func(x: s32, y: s32) -> s32;
+                }
+
+                world calc {
+                    export math-ops;
+                    export sub-numbers: func(x: s32, y: s32) -> s32;
+                }
+            "#,
+        );
+        let decoded = wit_component::decode(&component).unwrap();
+        let functions = list_wasm_component_functions(&decoded, None, None);
+        let mut options = defaults::<PathOptions>();
+        let mut paths = |path_case| {
+            options.path_case = path_case;
+
+            functions
+                .iter()
+                .map(|exported| exported.path(&options))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            paths(PathCase::Kebab),
+            ["/sub-numbers", "/math-ops/add-numbers"]
+        );
+        assert_eq!(
+            paths(PathCase::Snake),
+            ["/sub_numbers", "/math_ops/add_numbers"]
+        );
+        assert_eq!(
+            paths(PathCase::Camel),
+            ["/subNumbers", "/mathOps/addNumbers"]
+        );
+    }
+
+    #[actix_web::test]
+    async fn validates_the_parameters_before_calling_the_function() {
+        let mut options = defaults::<ValueOptions>();
+        options.strict_validation = true;
+        // The function traps, failing the requests that reach it with a 500
+        let endpoints = testing::endpoints(
+            r#"
+                package test:shapes;
+
+                interface types {
+                    record point { x: s32, y: s32 }
+                }
+
+                world shapes {
+                    use types.{point};
+
+                    export move: func(point: point, steps: u8);
+                }
+            "#,
+            options,
+        );
+        let request = actix_web::test::TestRequest::post().to_http_request();
+        let call = |body| {
+            let body = serde_json::from_value::<Body>(body).unwrap();
+
+            endpoints[0].call(&request, Some(body), false)
+        };
+
+        let error = call(json!({ "point": { "x": 1, "y": 2, "z": 3 }, "steps": 1 }))
+            .await
+            .unwrap_err();
+        assert_eq!(error.status, StatusCode::BAD_REQUEST);
+        assert_eq!(error.message, "the parameters don't match their schemas");
+        assert!(error.details.contains_key("errors"));
+
+        let error = call(json!({ "point": { "x": 1, "y": 2 }, "steps": 1 }))
+            .await
+            .unwrap_err();
+        assert_eq!(error.status, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn explains_the_bind_failures() {
+        let localhost = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let listener = bind(localhost, 0).unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let error = bind(localhost, port).unwrap_err().to_string();
+
+        assert!(
+            error.starts_with(&format!("Failed to bind 127.0.0.1:{}: ", port)),
+            "{}",
+            error
+        );
+        assert!(
+            error.ends_with("(the port may already be used by another process)"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn generates_the_same_spec_every_time() {
+        let spec = || {
+            let endpoints = testing::endpoints(VALUES_WIT, defaults());
+            let mut spec = serde_json::to_value(openapi(&endpoints, &defaults()).build()).unwrap();
+            add_webhooks(&mut spec, &endpoints);
+            spec::postprocess(&mut spec, &defaults()).unwrap();
+
+            spec::serialize(&spec, spec::Format::Json, false).unwrap()
+        };
+
+        let first = spec();
+
+        for _ in 0..5 {
+            assert_eq!(spec(), first);
+        }
+    }
+
+    #[actix_web::test]
+    async fn writes_the_spec_to_the_chosen_stream() {
+        let file = testing::TempFile::new(
+            "wasm",
+            testing::component(
+                r#"
+                    package test:calc;
+
+                    world calc {
+                        export add: func(x: s32, y: s32) -> s32;
+                    }
+                "#,
+            ),
+        );
+        let path = file.path();
+        let convert = |stream| async move {
+            let args = Cli::try_parse_from([
+                "wasm2openapi",
+                "--file",
+                path,
+                "--no-cache",
+                "convert",
+                "--output-stream",
+                stream,
+            ])
+            .unwrap();
+            let (mut stdout, mut stderr) = (vec![], vec![]);
+            let Ok(None) = start(args, &mut stdout, &mut stderr).await else {
+                panic!("the spec wasn't converted");
+            };
+
+            (
+                String::from_utf8(stdout).unwrap(),
+                String::from_utf8(stderr).unwrap(),
+            )
+        };
+
+        let (stdout, stderr) = convert("stdout").await;
+        let spec = serde_json::from_str::<serde_json::Value>(&stdout).unwrap();
+        assert_eq!(spec["paths"]["/add"]["post"]["operationId"], "add");
+        assert_eq!(stderr, "");
+
+        let (stdout, stderr) = convert("stderr").await;
+        assert_eq!(stdout, "");
+        assert_eq!(stderr, spec.to_string() + "\n");
+    }
+
+    #[actix_web::test]
+    async fn coerces_the_float_parameters_given_as_strings() {
+        let wit = r#"
+            package test:calc;
+
+            world calc {
+                export half: func(x: float64) -> float64;
+            }
+        "#;
+        let component = testing::component_with(
+            wit,
+            r#"
+                (module
+                    (func (export "half") (param f64) (result f64)
+                        (f64.mul (local.get 0) (f64.const 0.5))))
+            "#,
+        );
+        let component = &component;
+        let call = |coerce_strings, x| async move {
+            let mut options = defaults::<ValueOptions>();
+            options.coerce_strings = coerce_strings;
+            let endpoints = testing::endpoints_of(component, wit, options);
+            let request = actix_web::test::TestRequest::post().to_http_request();
+            let body = serde_json::from_value::<Body>(json!({ "x": x })).unwrap();
+
+            endpoints[0].call(&request, Some(body), false).await
+        };
+
+        let response = call(true, "3.14").await.unwrap();
+        let result = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        assert_eq!(result, "1.57");
+
+        let error = call(true, "abc").await.unwrap_err();
+        assert_eq!(error.status, StatusCode::BAD_REQUEST);
+        assert_eq!(error.message, "parameter `x`: `abc` is not a valid number");
+
+        let error = call(false, "3.14").await.unwrap_err();
+        assert_eq!(
+            error.message,
+            "parameter `x`: expected a number, got a string"
+        );
+    }
+
+    #[actix_web::test]
+    async fn describes_a_single_operation() {
+        let component = testing::component_with(testing::CALC_WIT, testing::CALC_WAT);
+        let server = testing::TestServer::start(&component, &[], &[]).await;
+        let describe = |path: &str| reqwest::get(format!("{}/__describe/{}", server.url, path));
+
+        let response = describe("add").await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let operation = response.json::<serde_json::Value>().await.unwrap();
+        assert_eq!(operation["operationId"], "add");
+        assert_eq!(
+            operation["requestBody"]["content"]["application/json"]["schema"]["oneOf"][0],
+            json!({
+                "type": "object",
+                "properties": {
+                    "x": { "type": "integer", "format": "int32" },
+                    "y": { "type": "integer", "format": "int32" },
+                },
+            })
+        );
+
+        let response = describe("sub").await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+        assert_eq!(
+            response.json::<serde_json::Value>().await.unwrap(),
+            json!({ "error": "no endpoint at `/sub`" })
+        );
+    }
+
+    #[actix_web::test]
+    async fn mounts_the_endpoints_and_the_spec_under_the_version() {
+        let component = testing::component_with(testing::CALC_WIT, testing::CALC_WAT);
+        let client = reqwest::Client::new();
+
+        for (options, serve, prefix) in [
+            (&[][..], &["--path-version", "v2"][..], "/v2"),
+            (&[], &["--path-version", "/v2/"], "/v2"),
+            (&[], &["--path-version"], "/v1"),
+            (&["--api-version", "3.1"], &["--path-version"], "/v3"),
+        ] {
+            let server = testing::TestServer::start(&component, options, serve).await;
+
+            let response = client
+                .post(format!("{}{}/add", server.url, prefix))
+                .json(&json!({ "x": 2, "y": 3 }))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.text().await.unwrap(), "5", "{:?}", serve);
+
+            let response = client
+                .post(format!("{}/add", server.url))
+                .json(&json!({ "x": 2, "y": 3 }))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+            let spec = client
+                .get(format!("{}{}/openapi.json", server.url, prefix))
+                .send()
+                .await
+                .unwrap()
+                .json::<serde_json::Value>()
+                .await
+                .unwrap();
+            assert_eq!(
+                spec["servers"][0]["url"],
+                format!("{}{}", server.url, prefix)
+            );
+            assert!(spec["paths"].get("/add").is_some());
+        }
+    }
+
+    #[test]
+    fn describes_the_documented_parameters() {
+        let endpoints = testing::endpoints(
+            r#"
+                package test:calc;
+
+                world calc {
+                    /// Add two integers.
+                    ///
+                    /// @param x The first term
+                    /// @param y The second term
+                    /// @param z Not a parameter
+                    export add: func(x: s32, y: s32) -> s32;
+                }
+            "#,
+            defaults(),
+        );
+
+        let spec = serde_json::to_value(openapi(&endpoints, &defaults()).build()).unwrap();
+
+        let operation = &spec["paths"]["/add"]["post"];
+        assert_eq!(operation["summary"], "Add two integers.");
+        assert_eq!(
+            operation["requestBody"]["content"]["application/json"]["schema"]["oneOf"][0]
+                ["properties"],
+            json!({
+                "x": { "type": "integer", "format": "int32", "description": "The first term" },
+                "y": { "type": "integer", "format": "int32", "description": "The second term" },
+            })
+        );
+    }
+
+    #[test]
+    fn encodes_the_non_finite_floats_by_policy() {
+        let mut options = defaults::<ValueOptions>();
+        let values = [
+            Val::Float32(f32::NAN),
+            Val::Float32(f32::INFINITY),
+            Val::Float32(f32::NEG_INFINITY),
+            Val::Float64(f64::NAN),
+            Val::Float64(f64::INFINITY),
+            Val::Float64(f64::NEG_INFINITY),
+            Val::Float32(0.5),
+            Val::Float64(-0.25),
+        ];
+        let to_json = |options: &ValueOptions| {
+            values
+                .iter()
+                .map(|value| Value(value.clone()).to_json(options))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            to_json(&options),
+            [
+                json!(null),
+                json!(null),
+                json!(null),
+                json!(null),
+                json!(null),
+                json!(null),
+                json!(0.5),
+                json!(-0.25),
+            ]
+        );
+
+        options.non_finite = NonFinitePolicy::String;
+        assert_eq!(
+            to_json(&options),
+            [
+                json!("NaN"),
+                json!("Infinity"),
+                json!("-Infinity"),
+                json!("NaN"),
+                json!("Infinity"),
+                json!("-Infinity"),
+                json!(0.5),
+                json!(-0.25),
+            ]
+        );
+    }
+
+    #[actix_web::test]
+    async fn configures_the_swagger_ui() {
+        let component = testing::component_with(testing::CALC_WIT, testing::CALC_WAT);
+        let initializer = |serve| {
+            let component = component.clone();
+
+            async move {
+                let server = testing::TestServer::start(&component, &[], serve).await;
+
+                reqwest::get(format!("{}/swagger-ui/swagger-initializer.js", server.url))
+                    .await
+                    .unwrap()
+                    .text()
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let script = initializer(&["--swagger"]).await;
+        assert!(script.contains(r#""deepLinking": true"#), "{}", script);
+        assert!(!script.contains("tryItOutEnabled"), "{}", script);
+        assert!(!script.contains("docExpansion"), "{}", script);
+
+        let script = initializer(&[
+            "--swagger",
+            "--swagger-deep-linking",
+            "false",
+            "--swagger-default-try-it-out",
+            "--swagger-doc-expansion",
+            "full",
+        ])
+        .await;
+        assert!(script.contains(r#""deepLinking": false"#), "{}", script);
+        assert!(script.contains(r#""tryItOutEnabled": true"#), "{}", script);
+        assert!(script.contains(r#""docExpansion": "full""#), "{}", script);
+    }
+
+    #[actix_web::test]
+    async fn calls_the_functions_with_enums_by_name_or_index() {
+        let wit = r#"
+            package test:paint;
+
+            interface types {
+                enum color { red, green, blue }
+            }
+
+            world paint {
+                use types.{color};
+
+                export next: func(color: color) -> color;
+            }
+        "#;
+        let component = testing::component_with(
+            wit,
+            r#"
+                (module
+                    (func (export "next") (param i32) (result i32)
+                        (i32.rem_u (i32.add (local.get 0) (i32.const 1)) (i32.const 3))))
+            "#,
+        );
+        let component = &component;
+        let next = |enum_as, color| async move {
+            let mut options = defaults::<ValueOptions>();
+            options.enum_as = enum_as;
+            let endpoints = testing::endpoints_of(component, wit, options);
+            let request = actix_web::test::TestRequest::post().to_http_request();
+            let body = serde_json::from_value::<Body>(json!({ "color": color })).unwrap();
+            let response = endpoints[0]
+                .call(&request, Some(body), false)
+                .await
+                .map_err(|e| e.message)?;
+            let result = actix_web::body::to_bytes(response.into_body())
+                .await
+                .unwrap();
+
+            Ok::<_, String>(serde_json::from_slice::<serde_json::Value>(&result).unwrap())
+        };
+
+        assert_eq!(next(EnumAs::Both, json!("red")).await, Ok(json!("green")));
+        assert_eq!(next(EnumAs::Both, json!(2)).await, Ok(json!("red")));
+        assert_eq!(
+            next(EnumAs::Both, json!(3)).await,
+            Err(String::from(
+                "parameter `color`: 3 is not a valid case index"
+            ))
+        );
+        assert_eq!(next(EnumAs::Int, json!(1)).await, Ok(json!(2)));
+        assert_eq!(next(EnumAs::String, json!("blue")).await, Ok(json!("red")));
+    }
+
+    #[test]
+    fn decodes_the_large_byte_lists_in_bulk() {
+        let types = testing::param_types(VALUES_WIT, "f");
+        let options = defaults::<ValueOptions>();
+        let items = (0..1 << 18).map(|i| json!(i % 256)).collect::<Vec<_>>();
+        let items = serde_json::Value::Array(items);
+        // The fastest of a few decodings, for the timings to be less noisy
+        let decode = |ty: &Type| {
+            (0..5)
+                .map(|_| {
+                    let items = items.clone();
+                    let start = Instant::now();
+                    let value = Value::from_json(items, ty, &options).unwrap();
+
+                    (start.elapsed(), value)
+                })
+                .min_by_key(|(elapsed, _)| *elapsed)
+                .unwrap()
+        };
+
+        let (bytes, value) = decode(&types[5]);
+        assert_eq!(value.to_json(&options), items);
+
+        // The same elements decoded as `list<u32>`, through the generic conversion
+        let (numbers, _) = decode(&types[4]);
+        assert!(
+            bytes < numbers,
+            "{:?} to decode the bytes, {:?} the numbers",
+            bytes,
+            numbers
+        );
+    }
+
+    #[test]
+    fn decodes_the_large_records() {
+        let types = testing::param_types(
+            r#"
+                package test:upload;
+
+                world upload {
+                    record file { name: string, data: list<u8>, tags: list<string> }
+
+                    export f: func(file: file);
+                }
+            "#,
+            "f",
+        );
+        let options = defaults::<ValueOptions>();
+        let file = json!({
+            "name": "x".repeat(1 << 20),
+            "data": (0..1 << 18).map(|i| i % 256).collect::<Vec<_>>(),
+            "tags": (0..1 << 12).map(|i| i.to_string()).collect::<Vec<_>>(),
+        });
+
+        let value = Value::from_json(file.clone(), &types[0], &options).unwrap();
+
+        assert_eq!(value.to_json(&options), file);
+    }
+
+    #[actix_web::test]
+    async fn calls_the_functions_concurrently() {
+        let wit = r#"
+            package test:spin;
+
+            world spin {
+                export spin: func(n: u32) -> u32;
+            }
+        "#;
+        let component = testing::component_with(
+            wit,
+            r#"
+                (module
+                    (func (export "spin") (param i32) (result i32)
+                        (local i32)
+                        (block
+                            (loop
+                                (br_if 1 (i32.ge_u (local.get 1) (local.get 0)))
+                                (local.set 1 (i32.add (local.get 1) (i32.const 1)))
+                                (br 0)))
+                        (local.get 1)))
+            "#,
+        );
+        let endpoints = testing::endpoints_of(&component, wit, defaults());
+        let request = actix_web::test::TestRequest::post().to_http_request();
+        let start = Instant::now();
+        // The time the call of a spin over `n` iterations returned at
+        let spin = |n: u32| {
+            let body = serde_json::from_value::<Body>(json!({ "n": n })).unwrap();
+            let call = endpoints[0].call(&request, Some(body), false);
+
+            async move {
+                call.await.unwrap();
+                start.elapsed()
+            }
+        };
+
+        let (slow, fast) = tokio::join!(spin(1_000_000_000), spin(1));
+        assert!(
+            fast < slow,
+            "{:?} for the fast call, {:?} the slow one",
+            fast,
+            slow
+        );
+    }
+
+    #[actix_web::test]
+    async fn serves_the_specs_of_several_components() {
+        let component = |ty: &str| {
+            testing::component(&format!(
+                r#"
+                    package test:shapes;
+
+                    world shapes {{
+                        record point {{ x: {ty}, y: {ty} }}
+
+                        export norm: func(point: point) -> {ty};
+                    }}
+                "#
+            ))
+        };
+        let files = [
+            testing::TempFile::new("wasm", component("s32")),
+            testing::TempFile::new("wasm", component("float64")),
+        ];
+        let names = files.each_ref().map(|file| {
+            let stem = std::path::Path::new(file.path()).file_stem().unwrap();
+
+            stem.to_str().unwrap().to_string()
+        });
+        let server =
+            testing::TestServer::start_file(files[0].path(), &["--file", files[1].path()], &[])
+                .await;
+        let get = |path: String| {
+            let url = format!("{}{}", server.url, path);
+
+            async move {
+                let response = reqwest::get(url).await.unwrap();
+
+                (
+                    response.status(),
+                    response.json::<serde_json::Value>().await.unwrap(),
+                )
+            }
+        };
+        let schema = |spec: &serde_json::Value, path: &str| {
+            spec["paths"][path]["post"]["requestBody"]["content"]["application/json"]["schema"]
+                ["oneOf"][0]["properties"]["point"]["$ref"]
+                .clone()
+        };
+
+        // The types of the same name are namespaced by component
+        let (_, spec) = get(String::from("/openapi.json")).await;
+        for (name, ty) in names.iter().zip(["integer", "number"]) {
+            let path = format!("/{}/norm", name);
+            let reference = format!("#/components/schemas/{}.point", name);
+
+            assert_eq!(schema(&spec, &path), json!(reference));
+            assert_eq!(
+                spec["components"]["schemas"][format!("{}.point", name)]["properties"]["x"]["type"],
+                ty
+            );
+        }
+
+        let (_, spec) = get(format!("/components/{}/openapi.json", names[1])).await;
+        let path = format!("/{}/norm", names[1]);
+        assert_eq!(spec["paths"].as_object().unwrap().len(), 1);
+        assert_eq!(schema(&spec, &path), "#/components/schemas/point");
+        assert_eq!(
+            spec["components"]["schemas"]["point"]["properties"]["x"]["type"],
+            "number"
+        );
+
+        let (status, _) = get(String::from("/components/other/openapi.json")).await;
+        assert_eq!(status, reqwest::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn mounts_the_server_under_the_base_path() {
+        let component = testing::component_with(testing::CALC_WIT, testing::CALC_WAT);
+        let server =
+            testing::TestServer::start(&component, &[], &["--base-path", "/api/v2", "--swagger"])
+                .await;
+        let client = reqwest::Client::new();
+        let add = |url: String| client.post(url).json(&json!({ "x": 2, "y": 3 })).send();
+
+        let spec = reqwest::get(format!("{}/api/v2/openapi.json", server.url))
+            .await
+            .unwrap()
+            .json::<serde_json::Value>()
+            .await
+            .unwrap();
+        let server_url = spec["servers"][0]["url"].as_str().unwrap();
+        assert_eq!(server_url, format!("{}/api/v2", server.url));
+
+        // The operations are served at the URLs the spec resolves them to
+        for path in spec["paths"].as_object().unwrap().keys() {
+            let response = add(format!("{}{}", server_url, path)).await.unwrap();
+            assert_eq!(response.text().await.unwrap(), "5", "{}", path);
+        }
+
+        let response = add(format!("{}/add", server.url)).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+        let script = reqwest::get(format!(
+            "{}/api/v2/swagger-ui/swagger-initializer.js",
+            server.url
+        ))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+        assert!(
+            script.contains("/api/v2/api-docs/openapi.json"),
+            "{}",
+            script
+        );
+    }
+}
@@ -0,0 +1,314 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use actix_web::http::header::RETRY_AFTER;
+use actix_web::http::StatusCode;
+use anyhow::{anyhow, bail, Context};
+
+use crate::error::ApiError;
+
+/// Call limits of a single endpoint, declared with the `@rate-limit <n>/<unit>` and
+/// `@max-concurrent <n>` doc annotations.
+#[derive(Debug, Default)]
+pub struct Limits {
+    rate: Option<RateLimit>,
+    concurrency: Option<ConcurrencyLimit>,
+}
+
+#[derive(Debug)]
+pub enum LimitExceeded {
+    Rate { retry_after: Duration },
+    Concurrency,
+}
+
+impl std::fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitExceeded::Rate { .. } => write!(f, "rate limit exceeded"),
+            LimitExceeded::Concurrency => write!(f, "too many concurrent calls"),
+        }
+    }
+}
+
+impl From<LimitExceeded> for ApiError {
+    fn from(e: LimitExceeded) -> Self {
+        let error = ApiError::new(StatusCode::TOO_MANY_REQUESTS, &e);
+
+        match e {
+            LimitExceeded::Rate { retry_after } => {
+                error.header((RETRY_AFTER, retry_after_secs(retry_after)))
+            }
+            LimitExceeded::Concurrency => error,
+        }
+    }
+}
+
+/// The whole seconds to wait before retrying, rounded up for the retry not to be early, and at
+/// least 1.
+fn retry_after_secs(retry_after: Duration) -> u64 {
+    let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+
+    secs.max(1)
+}
+
+impl Limits {
+    pub fn from_annotations<'a>(
+        annotations: impl Iterator<Item = (&'a str, &'a str)>,
+    ) -> anyhow::Result<Self> {
+        let mut limits = Self::default();
+
+        for (tag, value) in annotations {
+            match tag {
+                "rate-limit" => {
+                    limits.rate = Some(
+                        RateLimit::parse(value)
+                            .with_context(|| format!("invalid @rate-limit `{}`", value))?,
+                    )
+                }
+                "max-concurrent" => {
+                    let max = value
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|max| *max > 0)
+                        .ok_or_else(|| anyhow!("invalid @max-concurrent `{}`", value))?;
+
+                    limits.concurrency = Some(ConcurrencyLimit {
+                        max,
+                        current: AtomicUsize::new(0),
+                    })
+                }
+                _ => {}
+            }
+        }
+
+        Ok(limits)
+    }
+
+    /// Reserve a call slot, to be held for the whole duration of the call.
+    pub fn acquire(&self) -> Result<LimitGuard<'_>, LimitExceeded> {
+        let guard = LimitGuard {
+            concurrency: match &self.concurrency {
+                Some(limit) => Some(limit.acquire()?),
+                None => None,
+            },
+        };
+
+        if let Some(rate) = &self.rate {
+            rate.hit()?;
+        }
+
+        Ok(guard)
+    }
+}
+
+pub struct LimitGuard<'a> {
+    concurrency: Option<&'a ConcurrencyLimit>,
+}
+
+impl Drop for LimitGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(limit) = self.concurrency {
+            limit.current.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ConcurrencyLimit {
+    max: usize,
+    current: AtomicUsize,
+}
+
+impl ConcurrencyLimit {
+    fn acquire(&self) -> Result<&Self, LimitExceeded> {
+        self.current
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |current| {
+                (current < self.max).then_some(current + 1)
+            })
+            .map(|_| self)
+            .map_err(|_| LimitExceeded::Concurrency)
+    }
+}
+
+/// Sliding window rate limit.
+#[derive(Debug)]
+struct RateLimit {
+    max: usize,
+    window: Duration,
+    hits: Mutex<VecDeque<Instant>>,
+}
+
+impl RateLimit {
+    fn parse(value: &str) -> anyhow::Result<Self> {
+        let (max, unit) = value
+            .split_once('/')
+            .ok_or_else(|| anyhow!("expected `<count>/<unit>`"))?;
+        let max = max.trim().parse::<usize>()?;
+        let window = match unit.trim() {
+            "s" | "sec" | "second" => Duration::from_secs(1),
+            "m" | "min" | "minute" => Duration::from_secs(60),
+            "h" | "hour" => Duration::from_secs(60 * 60),
+            "d" | "day" => Duration::from_secs(24 * 60 * 60),
+            unit => bail!("unknown unit `{}`", unit),
+        };
+
+        if max == 0 {
+            bail!("the count must be greater than 0");
+        }
+
+        Ok(Self {
+            max,
+            window,
+            hits: Mutex::new(VecDeque::with_capacity(max)),
+        })
+    }
+
+    fn hit(&self) -> Result<(), LimitExceeded> {
+        self.hit_at(Instant::now())
+    }
+
+    fn hit_at(&self, now: Instant) -> Result<(), LimitExceeded> {
+        let mut hits = self.hits.lock().unwrap_or_else(|e| e.into_inner());
+
+        while hits
+            .front()
+            .is_some_and(|hit| now.duration_since(*hit) >= self.window)
+        {
+            hits.pop_front();
+        }
+
+        if hits.len() >= self.max {
+            let oldest = *hits.front().unwrap();
+
+            return Err(LimitExceeded::Rate {
+                retry_after: self.window - now.duration_since(oldest),
+            });
+        }
+
+        hits.push_back(now);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(annotations: &[(&str, &str)]) -> anyhow::Result<Limits> {
+        Limits::from_annotations(annotations.iter().copied())
+    }
+
+    #[test]
+    fn parses_the_annotations() {
+        let limits = limits(&[("rate-limit", "2/min"), ("max-concurrent", "3")]).unwrap();
+
+        let rate = limits.rate.unwrap();
+        assert_eq!(rate.max, 2);
+        assert_eq!(rate.window, Duration::from_secs(60));
+        assert_eq!(limits.concurrency.unwrap().max, 3);
+    }
+
+    #[test]
+    fn rejects_the_invalid_annotations() {
+        for (tag, value) in [
+            ("rate-limit", "2"),
+            ("rate-limit", "0/s"),
+            ("rate-limit", "2/week"),
+            ("rate-limit", "x/s"),
+            ("max-concurrent", "0"),
+            ("max-concurrent", "-1"),
+        ] {
+            assert!(limits(&[(tag, value)]).is_err(), "@{} {}", tag, value);
+        }
+    }
+
+    #[test]
+    fn refills_the_rate_once_the_window_has_passed() {
+        let rate = RateLimit::parse("2/s").unwrap();
+        let start = Instant::now();
+
+        assert!(rate.hit_at(start).is_ok());
+        assert!(rate.hit_at(start + Duration::from_millis(400)).is_ok());
+        match rate.hit_at(start + Duration::from_millis(600)) {
+            Err(LimitExceeded::Rate { retry_after }) => {
+                assert_eq!(retry_after, Duration::from_millis(400))
+            }
+            e => panic!("expected the rate to be exceeded, got {:?}", e),
+        }
+
+        // The first hit has left the window, the second one hasn't
+        assert!(rate.hit_at(start + Duration::from_secs(1)).is_ok());
+        assert!(rate.hit_at(start + Duration::from_millis(1200)).is_err());
+        assert!(rate.hit_at(start + Duration::from_millis(1400)).is_ok());
+    }
+
+    #[test]
+    fn rounds_the_retry_after_up() {
+        assert_eq!(retry_after_secs(Duration::from_millis(1)), 1);
+        assert_eq!(retry_after_secs(Duration::ZERO), 1);
+        assert_eq!(retry_after_secs(Duration::from_secs(2)), 2);
+        assert_eq!(retry_after_secs(Duration::from_millis(2001)), 3);
+        assert_eq!(retry_after_secs(Duration::from_millis(59_500)), 60);
+    }
+
+    #[test]
+    fn sets_the_retry_after_header() {
+        let error = ApiError::from(LimitExceeded::Rate {
+            retry_after: Duration::from_millis(1500),
+        });
+
+        assert_eq!(error.status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            error
+                .headers
+                .get(RETRY_AFTER)
+                .map(|value| value.to_str().unwrap()),
+            Some("2")
+        );
+    }
+
+    #[test]
+    fn releases_the_permits_when_the_calls_end() {
+        let limits = limits(&[("max-concurrent", "2")]).unwrap();
+
+        let first = limits.acquire().unwrap();
+        let second = limits.acquire().unwrap();
+        assert!(matches!(limits.acquire(), Err(LimitExceeded::Concurrency)));
+
+        drop(first);
+        let third = limits.acquire().unwrap();
+        assert!(limits.acquire().is_err());
+
+        drop(second);
+        drop(third);
+        assert_eq!(
+            limits
+                .concurrency
+                .as_ref()
+                .unwrap()
+                .current
+                .load(Ordering::SeqCst),
+            0
+        );
+    }
+
+    #[test]
+    fn releases_the_permit_of_a_call_over_the_rate() {
+        let limits = limits(&[("rate-limit", "1/h"), ("max-concurrent", "1")]).unwrap();
+
+        drop(limits.acquire().unwrap());
+        assert!(matches!(limits.acquire(), Err(LimitExceeded::Rate { .. })));
+        assert_eq!(
+            limits
+                .concurrency
+                .as_ref()
+                .unwrap()
+                .current
+                .load(Ordering::SeqCst),
+            0
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use serde_json::json;
+use utoipa::openapi::{KnownFormat, ObjectBuilder, RefOr, Schema, SchemaFormat, SchemaType};
+use wit_parser::Resolve;
+
+use crate::Type;
+
+/// A custom mapping of the WIT types to schemas, consulted before the built-in one, e.g. to give
+/// a `date-time` format to the strings holding timestamps.
+///
+/// The named types are mapped once, in the components of the OpenAPI definition, and referenced
+/// everywhere else.
+pub trait SchemaMapper: Send + Sync {
+    /// The schema of the type, or `None` to use the built-in one.
+    fn map(&self, ty: &Type, resolve: &Resolve) -> Option<RefOr<Schema>>;
+}
+
+/// The built-in mapping only.
+impl SchemaMapper for () {
+    fn map(&self, _: &Type, _: &Resolve) -> Option<RefOr<Schema>> {
+        None
+    }
+}
+
+impl<F> SchemaMapper for F
+where
+    F: Fn(&Type, &Resolve) -> Option<RefOr<Schema>> + Send + Sync,
+{
+    fn map(&self, ty: &Type, resolve: &Resolve) -> Option<RefOr<Schema>> {
+        self(ty, resolve)
+    }
+}
+
+/// The 64-bit integers as strings of digits, for `--bigint-as-string`.
+pub struct BigIntAsString;
+
+impl SchemaMapper for BigIntAsString {
+    fn map(&self, ty: &Type, _: &Resolve) -> Option<RefOr<Schema>> {
+        let pattern = match ty.0 {
+            wit_parser::Type::U64 => "^[0-9]+$",
+            wit_parser::Type::S64 => "^-?[0-9]+$",
+            _ => return None,
+        };
+
+        Some(RefOr::T(Schema::Object(
+            ObjectBuilder::new()
+                .schema_type(SchemaType::String)
+                .format(Some(SchemaFormat::KnownFormat(KnownFormat::Int64)))
+                .pattern(Some(pattern))
+                .example(Some(json!("0")))
+                .build(),
+        )))
+    }
+}
+
+/// The integers with a format naming their exact type (e.g. `uint8`) rather than the `int32` or
+/// `int64` one holding them, for `--openapi-version 3.1`, whose upgrade turns it into their range.
+pub struct IntegerFormats;
+
+impl SchemaMapper for IntegerFormats {
+    fn map(&self, ty: &Type, _: &Resolve) -> Option<RefOr<Schema>> {
+        let format = match ty.0 {
+            wit_parser::Type::U8 => "uint8",
+            wit_parser::Type::U16 => "uint16",
+            wit_parser::Type::U32 => "uint32",
+            wit_parser::Type::U64 => "uint64",
+            wit_parser::Type::S8 => "int8",
+            wit_parser::Type::S16 => "int16",
+            _ => return None,
+        };
+
+        Some(RefOr::T(Schema::Object(
+            ObjectBuilder::new()
+                .schema_type(SchemaType::Integer)
+                .format(Some(SchemaFormat::Custom(String::from(format))))
+                .build(),
+        )))
+    }
+}
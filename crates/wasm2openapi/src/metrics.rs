@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use actix_web::http::header::ContentType;
+use actix_web::{web, HttpResponse};
+
+/// The path of the Prometheus metrics, served with `--metrics`.
+pub const METRICS: &str = "/metrics";
+
+/// The upper bounds in seconds of the buckets of the call durations.
+const BUCKETS: [f64; 10] = [0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 1.0, 5.0];
+
+#[derive(Default)]
+struct FunctionMetrics {
+    calls: u64,
+    errors: u64,
+    /// The number of calls in each bucket, not cumulated.
+    buckets: [u64; BUCKETS.len()],
+    seconds: f64,
+}
+
+/// The counters of the function calls, by operation ID (e.g. `add` or `math.add`), shared by the
+/// workers as app data.
+#[derive(Default)]
+pub struct Metrics {
+    functions: Mutex<BTreeMap<String, FunctionMetrics>>,
+}
+
+impl Metrics {
+    /// Count a call of a function, as an error when it failed (e.g. trapped or timed out).
+    pub fn record(&self, function: &str, duration: Duration, failed: bool) {
+        let mut functions = self.functions.lock().unwrap_or_else(|e| e.into_inner());
+        if !functions.contains_key(function) {
+            functions.insert(function.to_string(), FunctionMetrics::default());
+        }
+        let metrics = functions.get_mut(function).unwrap();
+        let seconds = duration.as_secs_f64();
+
+        metrics.calls += 1;
+        metrics.errors += u64::from(failed);
+        metrics.seconds += seconds;
+        if let Some(bucket) = BUCKETS.iter().position(|le| seconds <= *le) {
+            metrics.buckets[bucket] += 1;
+        }
+    }
+
+    /// The metrics in the Prometheus text exposition format.
+    fn render(&self) -> String {
+        let functions = self.functions.lock().unwrap_or_else(|e| e.into_inner());
+        let mut text = String::new();
+
+        text.push_str("# HELP wasm2openapi_calls_total The calls of the functions.\n");
+        text.push_str("# TYPE wasm2openapi_calls_total counter\n");
+        for (function, metrics) in functions.iter() {
+            let _ = writeln!(
+                text,
+                "wasm2openapi_calls_total{{function=\"{}\"}} {}",
+                label(function),
+                metrics.calls
+            );
+        }
+
+        text.push_str(
+            "# HELP wasm2openapi_call_errors_total The calls of the functions that failed.\n",
+        );
+        text.push_str("# TYPE wasm2openapi_call_errors_total counter\n");
+        for (function, metrics) in functions.iter() {
+            let _ = writeln!(
+                text,
+                "wasm2openapi_call_errors_total{{function=\"{}\"}} {}",
+                label(function),
+                metrics.errors
+            );
+        }
+
+        text.push_str(
+            "# HELP wasm2openapi_call_duration_seconds The duration of the function calls.\n",
+        );
+        text.push_str("# TYPE wasm2openapi_call_duration_seconds histogram\n");
+        for (function, metrics) in functions.iter() {
+            let function = label(function);
+            let mut count = 0;
+
+            for (le, calls) in BUCKETS.iter().zip(metrics.buckets) {
+                count += calls;
+                let _ = writeln!(
+                    text,
+                    "wasm2openapi_call_duration_seconds_bucket{{function=\"{}\",le=\"{}\"}} {}",
+                    function, le, count
+                );
+            }
+            let _ = writeln!(
+                text,
+                "wasm2openapi_call_duration_seconds_bucket{{function=\"{}\",le=\"+Inf\"}} {}",
+                function, metrics.calls
+            );
+            let _ = writeln!(
+                text,
+                "wasm2openapi_call_duration_seconds_sum{{function=\"{}\"}} {}",
+                function, metrics.seconds
+            );
+            let _ = writeln!(
+                text,
+                "wasm2openapi_call_duration_seconds_count{{function=\"{}\"}} {}",
+                function, metrics.calls
+            );
+        }
+
+        text
+    }
+}
+
+/// A label value, escaped.
+fn label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+pub async fn metrics(metrics: web::Data<Metrics>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(ContentType(
+            "text/plain; version=0.0.4; charset=utf-8".parse().unwrap(),
+        ))
+        .body(metrics.render())
+}
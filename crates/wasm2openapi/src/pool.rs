@@ -0,0 +1,200 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+use anyhow::Context;
+use wasmtime::component::{Func, Instance, InstancePre};
+use wasmtime::{Engine, Store};
+
+use crate::timeout;
+use crate::wasi::{self, Host, WasiOptions};
+
+/// The instances of a component, created on demand up to a maximum so that concurrent calls run
+/// in parallel, each in its own store.
+pub struct InstancePool {
+    engine: Engine,
+    pre: InstancePre<Host>,
+    wasi: WasiOptions,
+    max_instances: usize,
+    max_fuel: Option<u64>,
+    /// The time a call may run for, checked with the epoch interruption of the engine.
+    call_timeout: Option<Duration>,
+    state: Mutex<State>,
+    released: Condvar,
+}
+
+struct State {
+    idle: Vec<(Store<Host>, Instance)>,
+    /// The number of instances, idle or in use.
+    instances: usize,
+}
+
+impl InstancePool {
+    pub fn new(
+        engine: &Engine,
+        pre: InstancePre<Host>,
+        wasi: WasiOptions,
+        max_instances: usize,
+        max_fuel: Option<u64>,
+        call_timeout: Option<Duration>,
+    ) -> Self {
+        Self {
+            engine: engine.clone(),
+            pre,
+            wasi,
+            max_instances: max_instances.max(1),
+            max_fuel,
+            call_timeout,
+            state: Mutex::new(State {
+                idle: vec![],
+                instances: 0,
+            }),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Take an idle instance, instantiating a new one if there is none and the maximum isn't
+    /// reached, or waiting for one to be released otherwise, with a new deadline for its calls.
+    pub fn get(&self) -> anyhow::Result<PooledInstance<'_>> {
+        let mut instance = self.take()?;
+
+        if let Some(timeout) = self.call_timeout {
+            instance.set_epoch_deadline(timeout::deadline(timeout));
+        }
+
+        Ok(instance)
+    }
+
+    fn take(&self) -> anyhow::Result<PooledInstance<'_>> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        loop {
+            if let Some((store, instance)) = state.idle.pop() {
+                return Ok(PooledInstance {
+                    pool: self,
+                    store: Some(store),
+                    instance,
+                    discarded: false,
+                });
+            }
+            if state.instances < self.max_instances {
+                break;
+            }
+
+            state = self.released.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+
+        state.instances += 1;
+        drop(state);
+
+        self.instantiate()
+            .map(|(store, instance)| PooledInstance {
+                pool: self,
+                store: Some(store),
+                instance,
+                discarded: false,
+            })
+            .inspect_err(|_| self.release(None))
+    }
+
+    fn instantiate(&self) -> anyhow::Result<(Store<Host>, Instance)> {
+        let mut store = Store::new(&self.engine, self.wasi.host()?);
+        if let Some(max_fuel) = self.max_fuel {
+            store.set_fuel(max_fuel)?;
+        }
+        if let Some(timeout) = self.call_timeout {
+            store.set_epoch_deadline(timeout::deadline(timeout));
+        }
+        let instance = match self.wasi.enabled() {
+            true => wasi::outside_runtime(|| self.pre.instantiate(&mut store)),
+            false => self.pre.instantiate(&mut store),
+        }
+        .context("Failed to instantiate component")?;
+
+        Ok((store, instance))
+    }
+
+    /// Give an instance back to the pool, or drop it to make room for a new one.
+    fn release(&self, instance: Option<(Store<Host>, Instance)>) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        match instance {
+            Some(instance) => state.idle.push(instance),
+            None => state.instances -= 1,
+        }
+
+        self.released.notify_one();
+    }
+}
+
+/// An instance taken from the pool, given back once dropped.
+pub struct PooledInstance<'a> {
+    pool: &'a InstancePool,
+    store: Option<Store<Host>>,
+    instance: Instance,
+    discarded: bool,
+}
+
+impl PooledInstance<'_> {
+    /// The function exported by the component, or by one of its instances.
+    pub fn func(&mut self, instance: Option<&str>, name: &str) -> Option<Func> {
+        let component_instance = self.instance;
+
+        match instance {
+            Some(instance) => component_instance
+                .exports(&mut **self)
+                .instance(instance)
+                .and_then(|mut instance| instance.func(name)),
+            None => component_instance.get_func(&mut **self, name),
+        }
+    }
+
+    /// Run a call into the instance, outside of the asynchronous runtime when the WASI host
+    /// functions may be called.
+    pub fn run<R: Send>(&mut self, f: impl FnOnce(&mut Store<Host>) -> R + Send) -> R {
+        let enabled = self.pool.wasi.enabled();
+        let store: &mut Store<Host> = self;
+
+        match enabled {
+            true => wasi::outside_runtime(|| f(store)),
+            false => f(store),
+        }
+    }
+
+    pub fn instance(&self) -> Instance {
+        self.instance
+    }
+
+    /// Drop the instance instead of giving it back to the pool, e.g. after a trap which leaves it
+    /// unusable.
+    pub fn discard(&mut self) {
+        self.discarded = true;
+    }
+}
+
+impl Deref for PooledInstance<'_> {
+    type Target = Store<Host>;
+
+    fn deref(&self) -> &Self::Target {
+        self.store.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledInstance<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.store.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledInstance<'_> {
+    fn drop(&mut self) {
+        let store = self.store.take().unwrap();
+
+        // The call of a panicking thread may have been cut off halfway through
+        self.pool
+            .release(match self.discarded || std::thread::panicking() {
+                true => None,
+                false => Some((store, self.instance)),
+            });
+    }
+}
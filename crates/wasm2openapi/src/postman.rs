@@ -0,0 +1,125 @@
+use serde_json::{json, Value};
+use utoipa::openapi::PathItemType;
+
+use crate::examples::Examples;
+use crate::spec::{self, DEFAULT_SERVER_URL};
+use crate::Endpoint;
+
+const SCHEMA: &str = "https://schema.getpostman.com/json/collection/v2.1.0/collection.json";
+
+/// A Postman v2.1 collection of the endpoints, with a folder per tag (i.e. per world or
+/// interface) and the request bodies templated from the schemas of the OpenAPI document `spec`.
+pub fn collection(spec: &Value, endpoints: &[Endpoint], examples: &Examples) -> Value {
+    let mut folders: Vec<(&Endpoint, Vec<Value>)> = vec![];
+
+    for endpoint in endpoints {
+        let request = request(endpoint, examples);
+
+        match folders
+            .iter_mut()
+            .find(|(first, _)| first.tag.name == endpoint.tag.name)
+        {
+            Some((_, requests)) => requests.push(request),
+            None => folders.push((endpoint, vec![request])),
+        }
+    }
+
+    json!({
+        "info": {
+            "name": spec.pointer("/info/title").cloned().unwrap_or_default(),
+            "description": spec.pointer("/info/description").cloned().unwrap_or_default(),
+            "schema": SCHEMA,
+        },
+        "item": folders
+            .into_iter()
+            .map(|(endpoint, requests)| json!({
+                "name": endpoint.tag.name,
+                "description": endpoint.tag.description,
+                "item": requests,
+            }))
+            .collect::<Vec<_>>(),
+        "variable": [{
+            "key": "baseUrl",
+            "value": spec.pointer("/servers/0/url").cloned().unwrap_or(json!(DEFAULT_SERVER_URL)),
+        }],
+    })
+}
+
+fn request(endpoint: &Endpoint, examples: &Examples) -> Value {
+    let (summary, description) = endpoint.parse_function_docs();
+    let path = endpoint
+        .path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+    let mut url = json!({
+        "raw": format!("{{{{baseUrl}}}}{}", endpoint.path),
+        "host": ["{{baseUrl}}"],
+        "path": path,
+    });
+    let mut request = json!({
+        "description": match description {
+            Some(description) => format!("{}\n\n{}", summary, description),
+            None => summary,
+        },
+    });
+
+    match endpoint.method {
+        PathItemType::Get => {
+            let query = endpoint
+                .function_parameters()
+                .into_iter()
+                .map(|parameter| {
+                    let parameter = serde_json::to_value(parameter).unwrap_or_default();
+                    let example = match parameter.get("example") {
+                        Some(example) => example.clone(),
+                        None => examples.example(&parameter["schema"]),
+                    };
+                    let value = match example {
+                        Value::String(value) => value,
+                        value => value.to_string(),
+                    };
+
+                    json!({ "key": parameter["name"], "value": value })
+                })
+                .collect::<Vec<_>>();
+
+            if !query.is_empty() {
+                let raw = query
+                    .iter()
+                    .map(|parameter| {
+                        format!(
+                            "{}={}",
+                            parameter["key"].as_str().unwrap_or_default(),
+                            parameter["value"].as_str().unwrap_or_default()
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("&");
+
+                url["raw"] = json!(format!("{{{{baseUrl}}}}{}?{}", endpoint.path, raw));
+                url["query"] = json!(query);
+            }
+
+            request["method"] = json!("GET");
+        }
+        _ => {
+            let operation = json!({ "requestBody": endpoint.function_request_body() });
+            let body = spec::request_body_example(&operation, examples).unwrap_or(json!({}));
+
+            request["method"] = json!("POST");
+            request["header"] = json!([{ "key": "Content-Type", "value": "application/json" }]);
+            request["body"] = json!({
+                "mode": "raw",
+                "raw": serde_json::to_string_pretty(&body).unwrap_or_default(),
+                "options": { "raw": { "language": "json" } },
+            });
+        }
+    }
+    request["url"] = url;
+
+    json!({
+        "name": endpoint.operation_id(),
+        "request": request,
+    })
+}
@@ -0,0 +1,141 @@
+use std::net::{IpAddr, SocketAddr};
+
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::HeaderMap;
+use actix_web::middleware::Logger;
+use actix_web::HttpMessage;
+use clap::ValueEnum;
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+const X_FORWARDED_PROTO: &str = "x-forwarded-proto";
+
+/// The format of the access log, where `client` and `scheme` honor the `X-Forwarded-*` headers
+/// set by the trusted proxies.
+const ACCESS_LOG_FORMAT: &str = r#"%{client}xi %{scheme}xi "%r" %s %b %T"#;
+
+/// The JSON format of the access log, where the custom replacements are JSON values and `%D` is
+/// the duration of the request in milliseconds.
+const JSON_ACCESS_LOG_FORMAT: &str = r#"{"client":%{client}xi,"scheme":%{scheme}xi,"method":%{method}xi,"path":%{path}xi,"status":%s,"size":%b,"duration_ms":%D,"function":%{function}xo,"trapped":%{trapped}xo}"#;
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    /// A line of text per request, e.g. `127.0.0.1 http "POST /add HTTP/1.1" 200 1 0.000512`
+    Plain,
+    /// A JSON object per request, along with the function it called and whether its guest
+    /// trapped
+    Json,
+}
+
+/// The function called to answer a request, stored in its extensions for the access log.
+#[derive(Clone, Debug)]
+pub struct CallLog {
+    pub function: String,
+    pub trapped: bool,
+}
+
+/// Log the requests, with the address and scheme of the clients behind the `trusted` proxies.
+pub fn access_log(trusted: &[IpAddr], format: LogFormat) -> Logger {
+    let (client, scheme) = (trusted.to_vec(), trusted.to_vec());
+    let json = format == LogFormat::Json;
+    // The JSON format quotes and escapes the strings
+    let text = move |s: &str| match json {
+        true => serde_json::Value::from(s).to_string(),
+        false => s.to_string(),
+    };
+    let logger = match format {
+        LogFormat::Plain => Logger::new(ACCESS_LOG_FORMAT),
+        LogFormat::Json => Logger::new(JSON_ACCESS_LOG_FORMAT)
+            .custom_request_replace("method", |request: &ServiceRequest| {
+                serde_json::Value::from(request.method().as_str()).to_string()
+            })
+            .custom_request_replace("path", |request: &ServiceRequest| {
+                serde_json::Value::from(request.path()).to_string()
+            })
+            .custom_response_replace("function", |response: &ServiceResponse| {
+                let log = response.request().extensions().get::<CallLog>().cloned();
+
+                serde_json::to_string(&log.map(|log| log.function)).unwrap_or_default()
+            })
+            .custom_response_replace("trapped", |response: &ServiceResponse| {
+                let log = response.request().extensions().get::<CallLog>().cloned();
+
+                log.is_some_and(|log| log.trapped).to_string()
+            }),
+    };
+
+    logger
+        .custom_request_replace("client", move |request: &ServiceRequest| {
+            client_ip(request.peer_addr(), request.headers(), &client)
+                .map_or_else(|| text("-"), |ip| text(&ip.to_string()))
+        })
+        .custom_request_replace("scheme", move |request: &ServiceRequest| {
+            text(&scheme_of(request.peer_addr(), request.headers(), &scheme))
+        })
+}
+
+/// The address of the client, read from `X-Forwarded-For` when the peer is a trusted proxy.
+///
+/// The addresses are read from right to left, skipping the trusted proxies, as the leftmost ones
+/// can be forged by the client.
+fn client_ip(peer: Option<SocketAddr>, headers: &HeaderMap, trusted: &[IpAddr]) -> Option<IpAddr> {
+    let peer = peer?.ip();
+
+    if !trusted.contains(&peer) {
+        return Some(peer);
+    }
+
+    let forwarded = headers
+        .get_all(X_FORWARDED_FOR)
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|ip| ip.trim().parse::<IpAddr>())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_default();
+
+    forwarded
+        .iter()
+        .rev()
+        .find(|ip| !trusted.contains(ip))
+        .or(forwarded.first())
+        .copied()
+        .or(Some(peer))
+}
+
+/// The scheme used by the client, read from `X-Forwarded-Proto` when the peer is a trusted proxy.
+fn scheme_of(peer: Option<SocketAddr>, headers: &HeaderMap, trusted: &[IpAddr]) -> String {
+    peer.filter(|peer| trusted.contains(&peer.ip()))
+        .and_then(|_| headers.get(X_FORWARDED_PROTO))
+        .and_then(|value| value.to_str().ok())
+        .map_or_else(
+            || String::from("http"),
+            |scheme| scheme.trim().to_lowercase(),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn reads_the_client_behind_the_trusted_proxies() {
+        let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+        let request = TestRequest::default()
+            .peer_addr(SocketAddr::new(proxy, 4242))
+            .insert_header((X_FORWARDED_FOR, "1.2.3.4, 203.0.113.7, 10.0.0.2"))
+            .insert_header((X_FORWARDED_PROTO, "HTTPS"))
+            .to_srv_request();
+        let (peer, headers) = (request.peer_addr(), request.headers());
+        let trusted = ["10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap()];
+
+        // The leftmost address can be forged, the rightmost untrusted one is the client
+        assert_eq!(
+            client_ip(peer, headers, &trusted),
+            Some("203.0.113.7".parse().unwrap())
+        );
+        assert_eq!(scheme_of(peer, headers, &trusted), "https");
+        // The headers of an untrusted peer are ignored
+        assert_eq!(client_ip(peer, headers, &[]), Some(proxy));
+        assert_eq!(scheme_of(peer, headers, &[]), "http");
+    }
+}
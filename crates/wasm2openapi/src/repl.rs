@@ -0,0 +1,148 @@
+use std::io::{BufRead, IsTerminal, Write};
+use std::ops::ControlFlow;
+
+use anyhow::{anyhow, Context};
+use wasmtime::AsContext;
+
+use crate::body::Body;
+use crate::Endpoint;
+
+/// Read `<function> [<JSON object of named parameters>]` lines from the standard input, and print
+/// the JSON results of the calls.
+pub fn run(endpoints: &[Endpoint]) -> anyhow::Result<()> {
+    let stdin = std::io::stdin();
+    let interactive = stdin.is_terminal();
+
+    if interactive {
+        println!("Type `help` to list the functions, `exit` to quit");
+    }
+
+    loop {
+        if interactive {
+            print!("> ");
+            std::io::stdout().flush()?;
+        }
+
+        let mut line = String::new();
+
+        if stdin.lock().read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+
+        match eval(endpoints, &line) {
+            ControlFlow::Continue(output) if output.is_empty() => {}
+            ControlFlow::Continue(output) => println!("{}", output),
+            ControlFlow::Break(()) => return Ok(()),
+        }
+    }
+}
+
+/// Evaluate a line of input, returning the text it prints, or breaking to quit.
+fn eval(endpoints: &[Endpoint], line: &str) -> ControlFlow<(), String> {
+    let (name, params) = match line.trim().split_once(char::is_whitespace) {
+        Some((name, params)) => (name, params.trim()),
+        None => (line.trim(), ""),
+    };
+
+    ControlFlow::Continue(match name {
+        "" => String::new(),
+        "help" => help(endpoints),
+        "exit" | "quit" => return ControlFlow::Break(()),
+        name => match call(endpoints, name, params) {
+            Ok(result) => result.to_string(),
+            Err(e) => format!("Error: {:#}", e),
+        },
+    })
+}
+
+fn help(endpoints: &[Endpoint]) -> String {
+    let lines = endpoints.iter().map(|endpoint| {
+        let params = endpoint
+            .prototype
+            .params
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        let (summary, _) = endpoint.parse_function_docs();
+        let signature = format!("{}({})", endpoint.operation_id(), params.join(", "));
+
+        match summary.is_empty() {
+            true => signature,
+            false => format!("{}\t{}", signature, summary),
+        }
+    });
+
+    lines.collect::<Vec<_>>().join("\n")
+}
+
+/// Call the function named after its operation ID or its path, e.g. `add` or `math.add`.
+fn call(endpoints: &[Endpoint], name: &str, params: &str) -> anyhow::Result<serde_json::Value> {
+    let endpoint = endpoints
+        .iter()
+        .find(|endpoint| {
+            endpoint.operation_id() == name || endpoint.path.trim_start_matches('/') == name
+        })
+        .ok_or_else(|| anyhow!("unknown function `{}`", name))?;
+    let payload = match params.is_empty() {
+        true => None,
+        false => Some(serde_json::from_str::<Body>(params).context("invalid parameters")?),
+    };
+    let mut store = endpoint.pool.get()?;
+    let callable = endpoint.callable(&mut store);
+    let parameters = endpoint
+        .decode_parameters(&callable, store.as_context(), payload)
+        .map_err(|e| anyhow!("{}", e))?;
+
+    if let Some(max) = endpoint.max_fuel {
+        store.set_fuel(max)?;
+    }
+
+    let results = endpoint
+        .invoke(&callable, &mut store, &parameters)
+        .inspect_err(|_| store.discard())
+        .context("the function call failed")?;
+
+    Ok(endpoint.results_to_json(&results))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{defaults, testing};
+
+    #[test]
+    fn evaluates_the_lines() {
+        let wit = r#"
+            package test:calc;
+
+            world calc {
+                export add: func(x: s32, y: s32) -> s32;
+            }
+        "#;
+        let component = testing::component_with(
+            wit,
+            r#"
+                (module
+                    (func (export "add") (param i32 i32) (result i32)
+                        (i32.add (local.get 0) (local.get 1))))
+            "#,
+        );
+        let endpoints = testing::endpoints_of(&component, wit, defaults());
+        let eval = |line| eval(&endpoints, line);
+
+        assert_eq!(
+            eval("add {\"x\":2,\"y\":3}\n"),
+            ControlFlow::Continue("5".to_string())
+        );
+        assert_eq!(
+            eval("add {\"x\":2}\n"),
+            ControlFlow::Continue("Error: missing parameter `y`".to_string())
+        );
+        assert_eq!(
+            eval("help\n"),
+            ControlFlow::Continue("add(x, y)".to_string())
+        );
+        assert_eq!(eval("\n"), ControlFlow::Continue(String::new()));
+        assert_eq!(eval("exit\n"), ControlFlow::Break(()));
+    }
+}
@@ -0,0 +1,80 @@
+use std::path::Path;
+
+use anyhow::Context;
+use serde_json::{json, Value};
+
+use crate::validation;
+
+const REF_PREFIX: &str = "#/components/schemas/";
+
+/// Write the `components/schemas` of the OpenAPI document `spec` to `<name>.schema.json` files in
+/// `dir`, as draft 4 JSON Schemas, the OpenAPI 3.0 dialect being mostly a subset of it, or as
+/// 2020-12 ones for an OpenAPI 3.1 document.
+pub fn emit(spec: &Value, dir: &Path) -> anyhow::Result<()> {
+    let Some(schemas) = spec
+        .pointer("/components/schemas")
+        .and_then(Value::as_object)
+    else {
+        return Ok(());
+    };
+
+    let dialect = match spec.get("openapi").and_then(Value::as_str) {
+        Some(version) if version.starts_with("3.1") => {
+            "https://json-schema.org/draft/2020-12/schema"
+        }
+        _ => "http://json-schema.org/draft-04/schema#",
+    };
+
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    for (name, schema) in schemas {
+        let mut schema = schema.clone();
+        validation::translate_nullable(&mut schema);
+        resolve_refs(&mut schema);
+
+        let mut file = json!({
+            "$schema": dialect,
+            "title": name,
+        });
+        match schema {
+            Value::Object(schema) => file.as_object_mut().unwrap().extend(schema),
+            schema => file["allOf"] = json!([schema]),
+        }
+
+        let path = dir.join(file_name(name));
+        std::fs::write(&path, serde_json::to_string_pretty(&file)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+
+    log::info!(
+        "Wrote the schemas of {} types to {}",
+        schemas.len(),
+        dir.display()
+    );
+
+    Ok(())
+}
+
+fn file_name(name: &str) -> String {
+    format!("{}.schema.json", name)
+}
+
+/// Point the references to the other components at their files, next to the referencing one.
+fn resolve_refs(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                match (key.as_str(), value) {
+                    ("$ref", Value::String(reference)) => {
+                        if let Some(name) = reference.strip_prefix(REF_PREFIX) {
+                            *reference = file_name(name);
+                        }
+                    }
+                    (_, value) => resolve_refs(value),
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(resolve_refs),
+        _ => {}
+    }
+}
@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use actix_web::dev::ServerHandle;
+use tokio::signal;
+
+/// Stop the server gracefully once SIGINT or SIGTERM is received: stop accepting connections,
+/// then wait up to `timeout` for the in-flight requests before cutting them off.
+pub async fn on_signal(server: ServerHandle, timeout: Duration) {
+    let name = wait().await;
+
+    log::info!(
+        "{} received, waiting up to {}s for the in-flight requests",
+        name,
+        timeout.as_secs()
+    );
+    server.stop(true).await;
+}
+
+/// Wait for SIGINT or SIGTERM, returning the name of the received signal.
+#[cfg(unix)]
+async fn wait() -> &'static str {
+    use signal::unix::{signal, SignalKind};
+
+    let mut sigterm = match signal(SignalKind::terminate()) {
+        Ok(sigterm) => sigterm,
+        Err(e) => {
+            log::warn!("Failed to listen for SIGTERM: {}", e);
+            let _ = signal::ctrl_c().await;
+
+            return "SIGINT";
+        }
+    };
+
+    tokio::select! {
+        _ = signal::ctrl_c() => "SIGINT",
+        _ = sigterm.recv() => "SIGTERM",
+    }
+}
+
+/// Wait for SIGINT, the only signal available on this platform.
+#[cfg(not(unix))]
+async fn wait() -> &'static str {
+    let _ = signal::ctrl_c().await;
+
+    "SIGINT"
+}
@@ -0,0 +1,224 @@
+use std::convert::Infallible;
+use std::io::Read;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use clap::Args;
+use reqwest::header::{HeaderName, HeaderValue};
+
+use crate::headers::parse_header;
+
+/// The magic number and version of a WebAssembly component binary, as opposed to a core module.
+const COMPONENT_PREAMBLE: [u8; 8] = [0x00, 0x61, 0x73, 0x6d, 0x0d, 0x00, 0x01, 0x00];
+
+/// The magic number and version of a WebAssembly core module.
+const MODULE_PREAMBLE: [u8; 8] = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+
+#[derive(Args, Debug, Clone)]
+pub struct FetchOptions {
+    /// Timeout in seconds when downloading the component from a URL
+    #[clap(long, default_value_t = 30)]
+    fetch_timeout: u64,
+
+    /// Header sent when downloading the component from a URL, as `<name>: <value>`
+    #[clap(long, value_parser = parse_header)]
+    fetch_header: Vec<(HeaderName, HeaderValue)>,
+}
+
+/// Where the component is loaded from.
+#[derive(Debug, Clone)]
+pub enum ComponentSource {
+    Path(PathBuf),
+    Url(String),
+    /// The standard input, given as `-`.
+    Stdin,
+}
+
+impl FromStr for ComponentSource {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "-" {
+            Ok(ComponentSource::Stdin)
+        } else if s.starts_with("http://") || s.starts_with("https://") {
+            Ok(ComponentSource::Url(s.to_string()))
+        } else {
+            Ok(ComponentSource::Path(PathBuf::from(s)))
+        }
+    }
+}
+
+impl ComponentSource {
+    /// Read the component bytes and check they are a component binary.
+    pub async fn load(&self, options: &FetchOptions) -> anyhow::Result<Vec<u8>> {
+        let data = match self {
+            ComponentSource::Path(path) => {
+                std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?
+            }
+            ComponentSource::Url(url) => fetch(url, options)
+                .await
+                .with_context(|| format!("Failed to download {}", url))?,
+            ComponentSource::Stdin => {
+                let mut data = vec![];
+
+                std::io::stdin()
+                    .read_to_end(&mut data)
+                    .context("Failed to read the standard input")?;
+                data
+            }
+        };
+
+        if data.is_empty() {
+            bail!("{} is empty", self);
+        }
+
+        if data.starts_with(&MODULE_PREAMBLE) {
+            bail!(
+                "{} is a WebAssembly core module, but a WebAssembly component is required\n\n\
+                 Build it as a component (e.g. with `cargo component build`), or wrap the module \
+                 with `wasm-tools component new module.wasm -o component.wasm`",
+                self
+            );
+        }
+
+        if !data.starts_with(&COMPONENT_PREAMBLE) {
+            bail!("{} is not a WebAssembly component", self);
+        }
+
+        Ok(data)
+    }
+
+    /// The name of the component, i.e. the stem of its file name (e.g. `add` for `add.wasm`).
+    pub fn name(&self) -> anyhow::Result<String> {
+        let file = match self {
+            ComponentSource::Path(path) => path.clone(),
+            ComponentSource::Url(url) => {
+                let path = url.split(['?', '#']).next().unwrap_or_default();
+
+                PathBuf::from(path.rsplit('/').next().unwrap_or_default())
+            }
+            ComponentSource::Stdin => return Ok(String::from("stdin")),
+        };
+
+        file.file_stem()
+            .and_then(|stem| stem.to_str())
+            .filter(|stem| !stem.is_empty())
+            .map(String::from)
+            .with_context(|| format!("Failed to name the component of {}", self))
+    }
+}
+
+impl std::fmt::Display for ComponentSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ComponentSource::Path(path) => write!(f, "{}", path.display()),
+            ComponentSource::Url(url) => write!(f, "{}", url),
+            ComponentSource::Stdin => write!(f, "The standard input"),
+        }
+    }
+}
+
+async fn fetch(url: &str, options: &FetchOptions) -> anyhow::Result<Vec<u8>> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(options.fetch_timeout))
+        .default_headers(options.fetch_header.iter().cloned().collect())
+        .build()?;
+    let response = client.get(url).send().await?.error_for_status()?;
+
+    Ok(response.bytes().await?.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{web, App, HttpResponse, HttpServer};
+    use serde_json::json;
+
+    use super::*;
+    use crate::defaults;
+    use crate::testing::{self, TempFile, TestServer};
+
+    #[actix_web::test]
+    async fn diagnoses_the_core_modules() {
+        let file = TempFile::new("wasm", wat::parse_str("(module)").unwrap());
+        let source = ComponentSource::from_str(file.path()).unwrap();
+
+        let error = source.load(&defaults()).await.unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "{} is a WebAssembly core module, but a WebAssembly component is required\n\n\
+                 Build it as a component (e.g. with `cargo component build`), or wrap the module \
+                 with `wasm-tools component new module.wasm -o component.wasm`",
+                file.path()
+            )
+        );
+    }
+
+    #[actix_web::test]
+    async fn loads_the_components_only() {
+        let component = testing::component("package test:empty;\n\nworld empty {}\n");
+        let file = TempFile::new("wasm", &component);
+        let source = ComponentSource::from_str(file.path()).unwrap();
+        assert_eq!(source.load(&defaults()).await.unwrap(), component);
+
+        let file = TempFile::new("wasm", "not wasm");
+        let source = ComponentSource::from_str(file.path()).unwrap();
+        assert_eq!(
+            source.load(&defaults()).await.unwrap_err().to_string(),
+            format!("{} is not a WebAssembly component", file.path())
+        );
+    }
+
+    #[actix_web::test]
+    async fn serves_the_components_downloaded_from_a_url() {
+        let component = testing::component_with(testing::CALC_WIT, testing::CALC_WAT);
+        let registry = {
+            let component = component.clone();
+
+            HttpServer::new(move || {
+                let component = component.clone();
+
+                App::new().route(
+                    "/calc.wasm",
+                    web::get().to(move || {
+                        let component = component.clone();
+
+                        async move { HttpResponse::Ok().body(component) }
+                    }),
+                )
+            })
+            .workers(1)
+            .bind(("127.0.0.1", 0))
+            .unwrap()
+        };
+        let url = format!("http://{}/calc.wasm", registry.addrs()[0]);
+        let registry = registry.run();
+        let handle = registry.handle();
+        actix_web::rt::spawn(registry);
+
+        let source = ComponentSource::from_str(&url).unwrap();
+        assert_eq!(source.load(&defaults()).await.unwrap(), component);
+
+        let missing = ComponentSource::from_str(&url.replace("calc", "missing")).unwrap();
+        let error = missing.load(&defaults()).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("Failed to download {}", url.replace("calc", "missing"))
+        );
+
+        let server = TestServer::start_file(&url, &[], &[]).await;
+        let response = reqwest::Client::new()
+            .post(format!("{}/add", server.url))
+            .json(&json!({ "x": 2, "y": 3 }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(response.text().await.unwrap(), "5");
+
+        handle.stop(false).await;
+    }
+}
@@ -0,0 +1,816 @@
+use anyhow::bail;
+use clap::{Args, ValueEnum};
+use serde_json::{json, Value};
+
+use crate::examples::{Examples, OptionExample};
+use crate::validation;
+
+/// The default JSON Schema dialect of the OpenAPI 3.1 schemas.
+const DIALECT_3_1: &str = "https://spec.openapis.org/oas/3.1/dialect/base";
+
+/// The server URL used in the generated code samples when the document declares none.
+pub const DEFAULT_SERVER_URL: &str = "http://127.0.0.1:8080";
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct SpecOptions {
+    /// Add code samples to every operation, as the `x-codeSamples` extension
+    #[clap(long, value_enum)]
+    code_samples: Vec<CodeSample>,
+
+    /// Add a generated example to every request body without one
+    #[clap(long)]
+    request_examples: bool,
+
+    /// Add a generated example to every success response without one
+    #[clap(long)]
+    response_examples: bool,
+
+    /// Inline every `$ref` at its use sites, for tools that can't follow references
+    #[clap(long)]
+    inline_refs: bool,
+
+    /// How optional values are rendered in the generated examples
+    #[clap(long, value_enum, default_value_t = OptionExample::Some)]
+    option_examples: OptionExample,
+
+    /// Check that the examples and defaults of the document validate against their schemas,
+    /// warning about the mismatches unless `error` is given
+    #[clap(long, value_enum, num_args = 0..=1, default_missing_value = "warn")]
+    validate_examples: Option<ExampleValidation>,
+
+    /// Specify the OpenAPI version of the document
+    #[clap(long, value_enum, default_value_t = OpenApiVersion::V3_0)]
+    openapi_version: OpenApiVersion,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OpenApiVersion {
+    #[default]
+    #[clap(name = "3.0")]
+    V3_0,
+    /// With JSON Schema 2020-12 schemas: `null` types instead of `nullable`, `prefixItems` for
+    /// the tuples, the exact ranges of the integers, and `webhooks` instead of the `x-webhooks`
+    /// extension
+    #[clap(name = "3.1")]
+    V3_1,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExampleValidation {
+    /// Log a warning for every mismatch
+    Warn,
+    /// Fail when any example doesn't validate
+    Error,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CodeSample {
+    Curl,
+}
+
+/// The serialization format of the OpenAPI document.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Yaml,
+    /// A Postman v2.1 collection of the endpoints, instead of an OpenAPI document
+    Postman,
+}
+
+/// Serialize the post-processed OpenAPI document, or the Postman collection, in the given format,
+/// indenting the JSON when `pretty` (YAML is always indented).
+pub fn serialize(spec: &Value, format: Format, pretty: bool) -> anyhow::Result<String> {
+    Ok(match format {
+        Format::Json | Format::Postman if pretty => serde_json::to_string_pretty(spec)?,
+        Format::Json | Format::Postman => serde_json::to_string(spec)?,
+        Format::Yaml => serde_yaml::to_string(spec)?,
+    })
+}
+
+impl SpecOptions {
+    pub fn option_examples(&self) -> OptionExample {
+        self.option_examples
+    }
+
+    pub fn openapi_version(&self) -> OpenApiVersion {
+        self.openapi_version
+    }
+}
+
+/// Post-process the serialized OpenAPI document, for what utoipa can't express.
+pub fn postprocess(spec: &mut Value, options: &SpecOptions) -> anyhow::Result<()> {
+    if options.inline_refs {
+        inline_refs(spec);
+    }
+    if options.request_examples {
+        add_request_examples(spec, options.option_examples);
+    }
+    if options.response_examples {
+        add_response_examples(spec, options.option_examples);
+    }
+    if !options.code_samples.is_empty() {
+        add_code_samples(spec, &options.code_samples, options.option_examples);
+    }
+    if let Some(validation) = options.validate_examples {
+        let mismatches = check_examples(spec)?;
+
+        for mismatch in &mismatches {
+            log::warn!("{}", mismatch);
+        }
+        if validation == ExampleValidation::Error && !mismatches.is_empty() {
+            bail!(
+                "{} example(s) don't validate against their schemas",
+                mismatches.len()
+            );
+        }
+    }
+    // Last, since the other steps work on the OpenAPI 3.0 document generated by utoipa
+    if options.openapi_version == OpenApiVersion::V3_1 {
+        upgrade_to_3_1(spec);
+    }
+
+    Ok(())
+}
+
+/// The generated document in the OpenAPI version of the options, with its references kept even
+/// under `--inline-refs`, whose inlining drops the schemas written by `--emit-schemas`.
+pub fn with_schemas(spec: &Value, options: &SpecOptions) -> Value {
+    let mut spec = spec.clone();
+
+    if options.openapi_version == OpenApiVersion::V3_1 {
+        upgrade_to_3_1(&mut spec);
+    }
+
+    spec
+}
+
+/// Convert the OpenAPI 3.0 document into an OpenAPI 3.1 one.
+fn upgrade_to_3_1(spec: &mut Value) {
+    upgrade_schemas(spec);
+
+    if let Some(spec) = spec.as_object_mut() {
+        spec.insert(String::from("openapi"), json!("3.1.0"));
+        spec.insert(String::from("jsonSchemaDialect"), json!(DIALECT_3_1));
+
+        if let Some(webhooks) = spec.remove("x-webhooks") {
+            spec.insert(String::from("webhooks"), webhooks);
+        }
+    }
+}
+
+/// Replace the `nullable` keyword with a `null` type, the arrays constrained to any of the tuple
+/// types with `prefixItems`, and the formats of the exact integer types with their ranges, in the
+/// schemas of the document.
+fn upgrade_schemas(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                match key.as_str() {
+                    // Not schemas
+                    "example" | "examples" | "default" | "enum" => {}
+                    // Not a schema, the keys are property names
+                    "properties" => value
+                        .as_object_mut()
+                        .into_iter()
+                        .flat_map(|properties| properties.values_mut())
+                        .for_each(upgrade_schemas),
+                    _ => upgrade_schemas(value),
+                }
+            }
+
+            let types = object
+                .get("items")
+                .and_then(|items| items.get("anyOf"))
+                .and_then(Value::as_array)
+                .cloned();
+            if let (Some(types), Some(min), Some(max)) = (
+                types,
+                object.get("minItems").and_then(Value::as_u64),
+                object.get("maxItems").and_then(Value::as_u64),
+            ) {
+                if types.len() as u64 == min && min == max {
+                    object.insert(String::from("prefixItems"), Value::Array(types));
+                    object.insert(String::from("items"), Value::Bool(false));
+                    object.remove("maxItems");
+                }
+            }
+
+            if let Some((format, minimum, maximum)) = object
+                .get("format")
+                .and_then(Value::as_str)
+                .filter(|_| object.get("type") == Some(&json!("integer")))
+                .and_then(integer_range)
+            {
+                match format {
+                    Some(format) => object.insert(String::from("format"), json!(format)),
+                    None => object.remove("format"),
+                };
+                object.insert(String::from("minimum"), minimum);
+                object.insert(String::from("maximum"), maximum);
+            }
+
+            if object.remove("nullable") == Some(Value::Bool(true)) {
+                match object.get_mut("type") {
+                    Some(Value::String(ty)) => {
+                        let ty = json!([ty, "null"]);
+
+                        object.insert(String::from("type"), ty);
+                        if let Some(Value::Array(values)) = object.get_mut("enum") {
+                            values.push(Value::Null);
+                        }
+                    }
+                    _ => {
+                        let schema = value.take();
+
+                        *value = json!({ "anyOf": [{ "type": "null" }, schema] });
+                    }
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(upgrade_schemas),
+        _ => {}
+    }
+}
+
+/// The range of an exact integer type given as a format by the `IntegerFormats` mapper, and the
+/// format of the OpenAPI format registry holding it, if any.
+fn integer_range(format: &str) -> Option<(Option<&'static str>, Value, Value)> {
+    Some(match format {
+        "int8" => (Some("int8"), json!(i8::MIN), json!(i8::MAX)),
+        "uint8" => (Some("uint8"), json!(0), json!(u8::MAX)),
+        "int16" => (Some("int16"), json!(i16::MIN), json!(i16::MAX)),
+        "uint16" => (Some("int32"), json!(0), json!(u16::MAX)),
+        "uint32" => (Some("int64"), json!(0), json!(u32::MAX)),
+        "uint64" => (None, json!(0), json!(u64::MAX)),
+        _ => return None,
+    })
+}
+
+/// Validate the examples and defaults of the operations and webhooks against their schemas,
+/// returning a message for every mismatch.
+fn check_examples(spec: &Value) -> anyhow::Result<Vec<String>> {
+    let mut mismatches = vec![];
+
+    for key in ["paths", "x-webhooks"] {
+        if let Some(value) = spec.get(key) {
+            check(value, &format!("/{}", key), spec, &mut mismatches)?;
+        }
+    }
+
+    Ok(mismatches)
+}
+
+/// Check the examples of a media type or parameter against its `schema`, and those of a schema
+/// against itself, at the JSON `pointer` of the document.
+fn check(
+    value: &Value,
+    pointer: &str,
+    spec: &Value,
+    mismatches: &mut Vec<String>,
+) -> anyhow::Result<()> {
+    match value {
+        Value::Object(object) => {
+            let schema = object.get("schema").unwrap_or(value);
+
+            for keyword in ["example", "default"] {
+                if let Some(example) = object.get(keyword) {
+                    let validator = validation::validator(schema, spec)?;
+
+                    mismatches.extend(
+                        validation::errors(&validator, example)
+                            .into_iter()
+                            .map(|error| format!("The {} at `{}`: {}", keyword, pointer, error)),
+                    );
+                }
+            }
+
+            for (key, value) in object {
+                let pointer = format!("{}/{}", pointer, escape(key));
+
+                match (key.as_str(), value) {
+                    ("example" | "default", _) => {}
+                    // Not a schema, the keys are property names.
+                    ("properties", Value::Object(properties)) => {
+                        for (name, value) in properties {
+                            check(
+                                value,
+                                &format!("{}/{}", pointer, escape(name)),
+                                spec,
+                                mismatches,
+                            )?;
+                        }
+                    }
+                    _ => check(value, &pointer, spec, mismatches)?,
+                }
+            }
+        }
+        Value::Array(values) => {
+            for (index, value) in values.iter().enumerate() {
+                check(value, &format!("{}/{}", pointer, index), spec, mismatches)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Escape a key as a JSON pointer segment.
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Prefix the names of the schemas with a namespace, e.g. `calc.point` for `point`, along with the
+/// references to them.
+pub fn namespace_schemas(spec: &mut Value, namespace: &str) {
+    const SCHEMAS: &str = "#/components/schemas/";
+
+    fn rename(value: &mut Value, namespace: &str) {
+        match value {
+            Value::Object(object) => {
+                for (key, value) in object.iter_mut() {
+                    match (key.as_str(), value) {
+                        ("$ref", Value::String(reference)) if reference.starts_with(SCHEMAS) => {
+                            *reference =
+                                format!("{}{}.{}", SCHEMAS, namespace, &reference[SCHEMAS.len()..]);
+                        }
+                        (_, value) => rename(value, namespace),
+                    }
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(|value| rename(value, namespace)),
+            _ => {}
+        }
+    }
+
+    if let Some(Value::Object(schemas)) = spec.pointer_mut("/components/schemas") {
+        *schemas = std::mem::take(schemas)
+            .into_iter()
+            .map(|(name, schema)| (format!("{}.{}", namespace, name), schema))
+            .collect();
+    }
+
+    rename(spec, namespace);
+}
+
+/// Replace the references with the referenced values, keeping them only on recursive types.
+fn inline_refs(spec: &mut Value) {
+    let snapshot = spec.clone();
+    let mut recursive = false;
+
+    for key in ["paths", "x-webhooks"] {
+        if let Some(value) = spec.get_mut(key) {
+            inline(value, &snapshot, &mut vec![], &mut recursive);
+        }
+    }
+
+    if recursive {
+        log::warn!("Recursive types can't be inlined, their references are kept");
+    } else if let Some(components) = spec.get_mut("components").and_then(Value::as_object_mut) {
+        components.remove("schemas");
+
+        if components.is_empty() {
+            spec.as_object_mut().unwrap().remove("components");
+        }
+    }
+}
+
+/// `refs` holds the references being expanded, to detect the recursive types.
+fn inline(value: &mut Value, spec: &Value, refs: &mut Vec<String>, recursive: &mut bool) {
+    match value {
+        Value::Object(object) => {
+            if let Some(reference) = object.get("$ref").and_then(Value::as_str) {
+                let reference = reference.to_string();
+
+                if refs.contains(&reference) {
+                    *recursive = true;
+                    return;
+                }
+
+                if let Some(target) = reference
+                    .strip_prefix('#')
+                    .and_then(|pointer| spec.pointer(pointer))
+                {
+                    *value = target.clone();
+                    refs.push(reference);
+                    inline(value, spec, refs, recursive);
+                    refs.pop();
+                }
+
+                return;
+            }
+
+            for value in object.values_mut() {
+                inline(value, spec, refs, recursive);
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                inline(value, spec, refs, recursive);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Iterate over the `(path, method, operation)` triples of the document.
+fn operations_mut(spec: &mut Value) -> impl Iterator<Item = (&String, &String, &mut Value)> {
+    spec.get_mut("paths")
+        .and_then(Value::as_object_mut)
+        .into_iter()
+        .flat_map(|paths| paths.iter_mut())
+        .filter_map(|(path, item)| Some((path, item.as_object_mut()?)))
+        .flat_map(|(path, item)| {
+            item.iter_mut()
+                .map(move |(method, operation)| (path, method, operation))
+        })
+}
+
+pub fn request_body_example(operation: &Value, examples: &Examples) -> Option<Value> {
+    operation
+        .pointer("/requestBody/content/application~1json")
+        .map(|content| match content.get("example") {
+            Some(example) => example.clone(),
+            None => examples.example(content.get("schema").unwrap_or(&Value::Null)),
+        })
+}
+
+fn add_request_examples(spec: &mut Value, options: OptionExample) {
+    let snapshot = spec.clone();
+    let examples = Examples::new(&snapshot, options);
+
+    for (_, _, operation) in operations_mut(spec) {
+        let example = request_body_example(operation, &examples);
+
+        if let (Some(example), Some(content)) = (
+            example,
+            operation
+                .pointer_mut("/requestBody/content/application~1json")
+                .and_then(Value::as_object_mut),
+        ) {
+            content.insert(String::from("example"), example);
+        }
+    }
+}
+
+fn add_response_examples(spec: &mut Value, options: OptionExample) {
+    let snapshot = spec.clone();
+    let examples = Examples::new(&snapshot, options);
+
+    for (_, _, operation) in operations_mut(spec) {
+        let successes = operation
+            .get_mut("responses")
+            .and_then(Value::as_object_mut)
+            .into_iter()
+            .flat_map(|responses| responses.iter_mut())
+            .filter(|(status, _)| status.starts_with('2'))
+            .filter_map(|(_, response)| response.pointer_mut("/content/application~1json"))
+            .filter_map(Value::as_object_mut);
+
+        for content in successes {
+            if !content.contains_key("example") {
+                let example = examples.example(content.get("schema").unwrap_or(&Value::Null));
+
+                content.insert(String::from("example"), example);
+            }
+        }
+    }
+}
+
+fn add_code_samples(spec: &mut Value, samples: &[CodeSample], options: OptionExample) {
+    let snapshot = spec.clone();
+    let examples = Examples::new(&snapshot, options);
+    let server_url = spec
+        .pointer("/servers/0/url")
+        .and_then(Value::as_str)
+        .unwrap_or(DEFAULT_SERVER_URL)
+        .to_string();
+
+    for (path, method, operation) in operations_mut(spec) {
+        let body = request_body_example(operation, &examples);
+        let url = format!(
+            "{}{}{}",
+            server_url,
+            path,
+            query_string(operation, &examples)
+        );
+        let samples = samples
+            .iter()
+            .map(|sample| match sample {
+                CodeSample::Curl => json!({
+                    "lang": "Shell",
+                    "label": "curl",
+                    "source": curl(method, &url, body.as_ref()),
+                }),
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(operation) = operation.as_object_mut() {
+            operation.insert(String::from("x-codeSamples"), Value::Array(samples));
+        }
+    }
+}
+
+/// The query string of the query parameters of an operation, valued with their examples, e.g.
+/// `?x=1&y=2`.
+fn query_string(operation: &Value, examples: &Examples) -> String {
+    let pairs = operation
+        .get("parameters")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|parameter| parameter.get("in").and_then(Value::as_str) == Some("query"))
+        .filter_map(|parameter| {
+            let name = parameter.get("name")?.as_str()?;
+            let value = match parameter.get("example") {
+                Some(example) => example.clone(),
+                None => examples.example(parameter.get("schema").unwrap_or(&Value::Null)),
+            };
+            // The strings are taken as is, the other values parsed as JSON
+            let value = match value {
+                Value::String(value) => value,
+                value => value.to_string(),
+            };
+
+            Some(format!(
+                "{}={}",
+                percent_encode(name),
+                percent_encode(&value)
+            ))
+        })
+        .collect::<Vec<_>>();
+
+    match pairs.is_empty() {
+        true => String::new(),
+        false => format!("?{}", pairs.join("&")),
+    }
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                char::from(byte).to_string()
+            }
+            byte => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+fn curl(method: &str, url: &str, body: Option<&Value>) -> String {
+    let mut command = format!("curl -X {} {}", method.to_uppercase(), shell_quote(url));
+
+    if let Some(body) = body {
+        command.push_str(" -H 'Content-Type: application/json' -d ");
+        command.push_str(&shell_quote(&body.to_string()));
+    }
+
+    command
+}
+
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{defaults, openapi, testing};
+
+    #[test]
+    fn upgrades_to_3_1() {
+        let mut spec = json!({
+            "openapi": "3.0.3",
+            "paths": {},
+            "x-webhooks": { "added": {} },
+            "components": {
+                "schemas": {
+                    "point": {
+                        "type": "object",
+                        "properties": {
+                            "x": { "type": "integer", "format": "uint8" },
+                            "y": { "type": "integer", "format": "int16", "nullable": true },
+                            "z": { "type": "integer", "format": "uint64" },
+                            "w": { "type": "integer", "format": "int32" },
+                        },
+                        "example": { "x": 1, "y": null, "nullable": true },
+                    },
+                    "pair": {
+                        "type": "array",
+                        "items": { "anyOf": [{ "type": "integer", "format": "uint32" }, { "type": "string" }] },
+                        "minItems": 2,
+                        "maxItems": 2,
+                    },
+                    "list": {
+                        "type": "array",
+                        "items": { "type": "integer", "format": "uint16" },
+                        "minItems": 2,
+                    },
+                    "color": { "type": "string", "enum": ["red", "blue"], "nullable": true },
+                    "maybe": { "nullable": true, "allOf": [{ "$ref": "#/components/schemas/point" }] },
+                    "digits": { "type": "string", "format": "uint64" },
+                },
+            },
+        });
+
+        upgrade_to_3_1(&mut spec);
+
+        assert_eq!(
+            spec,
+            json!({
+                "openapi": "3.1.0",
+                "jsonSchemaDialect": DIALECT_3_1,
+                "paths": {},
+                "webhooks": { "added": {} },
+                "components": {
+                    "schemas": {
+                        "point": {
+                            "type": "object",
+                            "properties": {
+                                "x": { "type": "integer", "format": "uint8", "minimum": 0, "maximum": 255 },
+                                "y": {
+                                    "type": ["integer", "null"],
+                                    "format": "int16",
+                                    "minimum": -32768,
+                                    "maximum": 32767,
+                                },
+                                "z": { "type": "integer", "minimum": 0, "maximum": u64::MAX },
+                                "w": { "type": "integer", "format": "int32" },
+                            },
+                            "example": { "x": 1, "y": null, "nullable": true },
+                        },
+                        "pair": {
+                            "type": "array",
+                            "prefixItems": [
+                                { "type": "integer", "format": "int64", "minimum": 0, "maximum": u32::MAX },
+                                { "type": "string" },
+                            ],
+                            "items": false,
+                            "minItems": 2,
+                        },
+                        "list": {
+                            "type": "array",
+                            "items": { "type": "integer", "format": "int32", "minimum": 0, "maximum": 65535 },
+                            "minItems": 2,
+                        },
+                        "color": { "type": ["string", "null"], "enum": ["red", "blue", null] },
+                        "maybe": {
+                            "anyOf": [
+                                { "type": "null" },
+                                { "allOf": [{ "$ref": "#/components/schemas/point" }] },
+                            ],
+                        },
+                        "digits": { "type": "string", "format": "uint64" },
+                    },
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn inlines_the_refs() {
+        let mut spec = json!({
+            "openapi": "3.0.3",
+            "paths": {
+                "/move": {
+                    "post": {
+                        "requestBody": {
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "object",
+                                        "properties": {
+                                            "shape": { "$ref": "#/components/schemas/shape" },
+                                        },
+                                    },
+                                },
+                            },
+                        },
+                    },
+                },
+            },
+            "components": {
+                "schemas": {
+                    "point": { "type": "object", "properties": { "x": { "type": "integer" } } },
+                    "shape": {
+                        "type": "array",
+                        "items": { "$ref": "#/components/schemas/point" },
+                    },
+                },
+            },
+        });
+        let mut options = defaults::<SpecOptions>();
+        options.inline_refs = true;
+
+        postprocess(&mut spec, &options).unwrap();
+
+        assert!(!spec.to_string().contains("$ref"));
+        assert_eq!(spec.get("components"), None);
+        assert_eq!(
+            spec["paths"]["/move"]["post"]["requestBody"]["content"]["application/json"]["schema"]
+                ["properties"]["shape"],
+            json!({
+                "type": "array",
+                "items": { "type": "object", "properties": { "x": { "type": "integer" } } },
+            })
+        );
+    }
+
+    #[test]
+    fn adds_the_curl_code_samples() {
+        let endpoints = testing::endpoints(
+            r#"
+                package test:calc;
+
+                world calc {
+                    export add: func(x: s32, y: s32) -> s32;
+                }
+            "#,
+            defaults(),
+        );
+        let mut spec = serde_json::to_value(openapi(&endpoints, &defaults()).build()).unwrap();
+        let mut options = defaults::<SpecOptions>();
+        options.code_samples = vec![CodeSample::Curl];
+
+        postprocess(&mut spec, &options).unwrap();
+
+        assert_eq!(
+            spec["paths"]["/add"]["post"]["x-codeSamples"],
+            json!([{
+                "lang": "Shell",
+                "label": "curl",
+                "source": "curl -X POST 'http://127.0.0.1:8080/add' \
+                           -H 'Content-Type: application/json' -d '{\"x\":0,\"y\":0}'",
+            }])
+        );
+    }
+
+    #[test]
+    fn detects_the_invalid_examples() {
+        let mut spec = json!({
+            "openapi": "3.0.3",
+            "paths": {
+                "/paint": {
+                    "post": {
+                        "requestBody": {
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "object",
+                                        "properties": {
+                                            "red": { "type": "integer", "minimum": 0, "maximum": 255 },
+                                        },
+                                    },
+                                    "example": { "red": 300 },
+                                },
+                            },
+                        },
+                    },
+                },
+            },
+        });
+
+        let mismatches = check_examples(&spec).unwrap();
+
+        assert_eq!(mismatches.len(), 1);
+        assert!(mismatches[0].starts_with(
+            "The example at `/paths/~1paint/post/requestBody/content/application~1json`"
+        ));
+
+        let mut options = defaults::<SpecOptions>();
+        options.validate_examples = Some(ExampleValidation::Error);
+
+        let error = postprocess(&mut spec, &options).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "1 example(s) don't validate against their schemas"
+        );
+    }
+
+    #[test]
+    fn adds_the_query_strings_to_the_get_code_samples() {
+        let endpoints = testing::endpoints(
+            r#"
+                package test:greet;
+
+                world greet {
+                    /// @method get
+                    /// @example-request {"name": "Ada Lovelace"}
+                    export greet: func(name: string, times: u8) -> string;
+                }
+            "#,
+            defaults(),
+        );
+        let mut spec = serde_json::to_value(openapi(&endpoints, &defaults()).build()).unwrap();
+        let mut options = defaults::<SpecOptions>();
+        options.code_samples = vec![CodeSample::Curl];
+
+        postprocess(&mut spec, &options).unwrap();
+
+        assert_eq!(
+            spec["paths"]["/greet"]["get"]["x-codeSamples"][0]["source"],
+            "curl -X GET 'http://127.0.0.1:8080/greet?name=Ada%20Lovelace&times=0'"
+        );
+    }
+}
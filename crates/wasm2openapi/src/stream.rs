@@ -0,0 +1,73 @@
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use actix_web::body::{BodySize, MessageBody};
+use actix_web::web::Bytes;
+use wasmtime::component::List;
+
+use crate::{Value, ValueOptions};
+
+/// The number of elements from which a returned list is streamed, rather than serialized at once.
+pub const MIN_STREAMED_ITEMS: usize = 1024;
+
+/// The size from which the serialized elements are sent as a chunk.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// A JSON array response body, serialized a chunk of elements at a time as it is sent.
+pub struct ListBody {
+    list: List,
+    /// The index of the next element to serialize.
+    next: usize,
+    done: bool,
+    options: Arc<ValueOptions>,
+}
+
+impl ListBody {
+    pub fn new(list: List, options: Arc<ValueOptions>) -> Self {
+        Self {
+            list,
+            next: 0,
+            done: false,
+            options,
+        }
+    }
+}
+
+impl MessageBody for ListBody {
+    type Error = Infallible;
+
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let body = self.get_mut();
+        if body.done {
+            return Poll::Ready(None);
+        }
+
+        let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+        if body.next == 0 {
+            chunk.push(b'[');
+        }
+        while body.next < body.list.len() && chunk.len() < CHUNK_SIZE {
+            if body.next > 0 {
+                chunk.push(b',');
+            }
+            let value = Value::val_to_json(&body.list[body.next], &body.options);
+            chunk.extend_from_slice(value.to_string().as_bytes());
+            body.next += 1;
+        }
+        if body.next == body.list.len() {
+            chunk.push(b']');
+            body.done = true;
+        }
+
+        Poll::Ready(Some(Ok(Bytes::from(chunk))))
+    }
+}
@@ -0,0 +1,314 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context};
+use wasmtime::component::{Component, Linker, Val};
+use wit_parser::{Function, Resolve, Results, TypeDefKind, WorldItem};
+
+use crate::{Type, Value, ValueOptions};
+
+/// The fixed results of imported functions, read from a YAML or JSON file mapping their names
+/// (e.g. `log` or `my:host/logging#log`) to their result, or to an object of their named results.
+#[derive(Default)]
+pub struct ImportValues(HashMap<String, serde_json::Value>);
+
+impl ImportValues {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let file = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let values = serde_yaml::from_str(&file)
+            .with_context(|| format!("Failed to parse the import values of {}", path.display()))?;
+
+        Ok(Self(values))
+    }
+}
+
+/// How a stubbed import computes one of its results.
+#[derive(Clone)]
+enum StubResult {
+    /// Return the argument at this index, which has the same type.
+    Echo(usize),
+    /// Return the default value of the type, or the value given for the import.
+    Value(Val),
+    /// The type has no default value, and no argument to echo.
+    Unsupported(&'static str),
+}
+
+/// Define the functions imported by the component with a `values` entry with stubs returning it,
+/// and, when `all`, every other one with a stub returning its arguments when their types match the
+/// results, or defaults (zeroes, empty strings) otherwise.
+pub fn stub_imports<T>(
+    linker: &mut Linker<T>,
+    component: &Component,
+    resolve: &Resolve,
+    values: &ImportValues,
+    all: bool,
+    options: &ValueOptions,
+) -> anyhow::Result<()> {
+    let stubs = Stubs {
+        component,
+        resolve,
+        values,
+        all,
+        options,
+    };
+
+    for (_, world) in resolve.worlds.iter() {
+        for (key, item) in world.imports.iter() {
+            match item {
+                WorldItem::Function(function) => stubs.stub(&mut linker.root(), None, function)?,
+                WorldItem::Interface(id) => {
+                    let name = resolve.name_world_key(key);
+                    let functions = resolve.interfaces[*id]
+                        .functions
+                        .values()
+                        .filter(|function| {
+                            all || values.0.contains_key(&import_name(Some(&name), function))
+                        })
+                        .collect::<Vec<_>>();
+                    if functions.is_empty() {
+                        continue;
+                    }
+
+                    let mut instance = linker
+                        .instance(&name)
+                        .with_context(|| format!("Failed to stub the `{}` import", name))?;
+
+                    for function in functions {
+                        stubs.stub(&mut instance, Some(&name), function)?;
+                    }
+                }
+                WorldItem::Type(_) => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The name of an imported function, as given in the import values.
+fn import_name(interface: Option<&str>, function: &Function) -> String {
+    match interface {
+        Some(interface) => format!("{}#{}", interface, function.name),
+        None => function.name.clone(),
+    }
+}
+
+struct Stubs<'a> {
+    component: &'a Component,
+    resolve: &'a Resolve,
+    values: &'a ImportValues,
+    all: bool,
+    options: &'a ValueOptions,
+}
+
+impl Stubs<'_> {
+    fn stub<T>(
+        &self,
+        linker: &mut wasmtime::component::LinkerInstance<T>,
+        interface: Option<&str>,
+        function: &Function,
+    ) -> anyhow::Result<()> {
+        let name = import_name(interface, function);
+        let results = match self.values.0.get(&name) {
+            Some(value) => self
+                .values(function, value)
+                .with_context(|| format!("Invalid import value of `{}`", name))?,
+            None if self.all => self.defaults(function),
+            None => return Ok(()),
+        };
+
+        linker
+            .func_new(self.component, &function.name, {
+                let name = name.clone();
+
+                move |_, params, values| {
+                    log::info!("Stubbed call to `{}`", name);
+
+                    for (value, result) in values.iter_mut().zip(&results) {
+                        *value = match result {
+                            StubResult::Echo(index) => params[*index].clone(),
+                            StubResult::Value(val) => val.clone(),
+                            StubResult::Unsupported(kind) => {
+                                bail!("`{}` can't be stubbed: no default {} value", name, kind)
+                            }
+                        };
+                    }
+
+                    Ok(())
+                }
+            })
+            .with_context(|| format!("Failed to stub `{}`", name))
+    }
+
+    fn defaults(&self, function: &Function) -> Vec<StubResult> {
+        function
+            .results
+            .iter_types()
+            .map(
+                |ty| match function.params.iter().position(|(_, param)| param == ty) {
+                    Some(index) => StubResult::Echo(index),
+                    None => match default_val(self.resolve, ty) {
+                        Some(val) => StubResult::Value(val),
+                        None => StubResult::Unsupported(Type(*ty).kind(self.resolve)),
+                    },
+                },
+            )
+            .collect()
+    }
+
+    /// The results given by the import value of a function, which is its single result, or an
+    /// object of its named results.
+    fn values(
+        &self,
+        function: &Function,
+        value: &serde_json::Value,
+    ) -> anyhow::Result<Vec<StubResult>> {
+        let results = match &function.results {
+            Results::Anon(ty) => vec![(ty, value)],
+            Results::Named(results) => results
+                .iter()
+                .map(|(name, ty)| {
+                    value
+                        .get(name)
+                        .map(|value| (ty, value))
+                        .ok_or_else(|| anyhow!("missing the `{}` result", name))
+                })
+                .collect::<anyhow::Result<_>>()?,
+        };
+
+        results
+            .into_iter()
+            .map(|(ty, value)| {
+                let kind = Type(*ty).kind(self.resolve);
+                let ty = primitive_type(self.resolve, ty)
+                    .ok_or_else(|| anyhow!("a {} result can't be given a value", kind))?;
+                let value = Value::from_json(value.clone(), &ty, self.options)
+                    .map_err(|e| anyhow!("{}", e))?;
+
+                Ok(StubResult::Value(value.0))
+            })
+            .collect()
+    }
+}
+
+/// The runtime type of a primitive WIT type, or of an alias of one.
+fn primitive_type(resolve: &Resolve, ty: &wit_parser::Type) -> Option<wasmtime::component::Type> {
+    use wasmtime::component::Type;
+
+    Some(match ty {
+        wit_parser::Type::Bool => Type::Bool,
+        wit_parser::Type::U8 => Type::U8,
+        wit_parser::Type::U16 => Type::U16,
+        wit_parser::Type::U32 => Type::U32,
+        wit_parser::Type::U64 => Type::U64,
+        wit_parser::Type::S8 => Type::S8,
+        wit_parser::Type::S16 => Type::S16,
+        wit_parser::Type::S32 => Type::S32,
+        wit_parser::Type::S64 => Type::S64,
+        wit_parser::Type::Float32 => Type::Float32,
+        wit_parser::Type::Float64 => Type::Float64,
+        wit_parser::Type::Char => Type::Char,
+        wit_parser::Type::String => Type::String,
+        wit_parser::Type::Id(id) => match &resolve.types[*id].kind {
+            TypeDefKind::Type(ty) => return primitive_type(resolve, ty),
+            _ => return None,
+        },
+    })
+}
+
+fn default_val(resolve: &Resolve, ty: &wit_parser::Type) -> Option<Val> {
+    Some(match ty {
+        wit_parser::Type::Bool => Val::Bool(false),
+        wit_parser::Type::U8 => Val::U8(0),
+        wit_parser::Type::U16 => Val::U16(0),
+        wit_parser::Type::U32 => Val::U32(0),
+        wit_parser::Type::U64 => Val::U64(0),
+        wit_parser::Type::S8 => Val::S8(0),
+        wit_parser::Type::S16 => Val::S16(0),
+        wit_parser::Type::S32 => Val::S32(0),
+        wit_parser::Type::S64 => Val::S64(0),
+        wit_parser::Type::Float32 => Val::Float32(0.0),
+        wit_parser::Type::Float64 => Val::Float64(0.0),
+        wit_parser::Type::Char => Val::Char('\0'),
+        wit_parser::Type::String => Val::String("".into()),
+        wit_parser::Type::Id(id) => match &resolve.types[*id].kind {
+            TypeDefKind::Type(ty) => return default_val(resolve, ty),
+            _ => return None,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use serde_json::json;
+
+    use crate::testing::{self, TempFile, TestServer};
+    use crate::{start, Cli};
+
+    const WIT: &str = r#"
+        package test:offsets;
+
+        world offsets {
+            import offset: func() -> s32;
+            import echo: func(x: s32) -> s32;
+
+            export add: func(x: s32, y: s32) -> s32;
+        }
+    "#;
+
+    /// Add the echoed `x`, `y` and the offset.
+    const WAT: &str = r#"
+        (module
+            (import "$root" "offset" (func $offset (result i32)))
+            (import "$root" "echo" (func $echo (param i32) (result i32)))
+            (func (export "add") (param i32 i32) (result i32)
+                (i32.add
+                    (i32.add (call $echo (local.get 0)) (local.get 1))
+                    (call $offset))))
+    "#;
+
+    async fn add(server: &TestServer) -> String {
+        reqwest::Client::new()
+            .post(format!("{}/add", server.url))
+            .json(&json!({ "x": 2, "y": 3 }))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap()
+    }
+
+    #[actix_web::test]
+    async fn stubs_the_imports() {
+        let component = testing::component_with(WIT, WAT);
+
+        let server = TestServer::start(&component, &["--stub-imports"], &[]).await;
+        assert_eq!(add(&server).await, "5");
+
+        let values = TempFile::new("yaml", "offset: 10\n");
+        let server = TestServer::start(
+            &component,
+            &["--stub-imports", "--import-values", values.path()],
+            &[],
+        )
+        .await;
+        assert_eq!(add(&server).await, "15");
+
+        let file = TempFile::new("wasm", &component);
+        let args = Cli::try_parse_from([
+            "wasm2openapi",
+            "--file",
+            file.path(),
+            "--no-cache",
+            "convert",
+        ])
+        .unwrap();
+        let Err(error) = start(args, &mut vec![], &mut vec![]).await else {
+            panic!("the component was instantiated without its imports");
+        };
+        assert_eq!(error.to_string(), "Failed to instantiate component");
+    }
+}
@@ -0,0 +1,222 @@
+//! Components and endpoints built from WIT documents, for the unit tests.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use actix_web::dev::ServerHandle;
+use clap::Parser;
+
+use wasmtime::component::{Component, Linker, Type};
+use wasmtime::{Config, Engine};
+use wit_component::{ComponentEncoder, DecodedWasm, StringEncoding};
+use wit_parser::{Resolve, UnresolvedPackage, WorldId};
+
+use crate::pool::InstancePool;
+use crate::wasi::{Host, WasiOptions};
+use crate::{
+    defaults, get_endpoints, list_wasm_component_functions, start, Cli, Endpoint, SchemaMapper,
+    ValueOptions,
+};
+
+/// A WIT document of a function adding two integers.
+pub const CALC_WIT: &str = r#"
+    package test:calc;
+
+    world calc {
+        export add: func(x: s32, y: s32) -> s32;
+    }
+"#;
+
+/// A core module implementing [`CALC_WIT`].
+pub const CALC_WAT: &str = r#"
+    (module
+        (func (export "add") (param i32 i32) (result i32)
+            (i32.add (local.get 0) (local.get 1))))
+"#;
+
+/// A component implementing the single world of a WIT document with functions that trap.
+pub fn component(wit: &str) -> Vec<u8> {
+    let (resolve, world) = world(wit);
+    let module = wit_component::dummy_module(&resolve, world);
+
+    encode(&module, &resolve, world)
+}
+
+/// A component implementing the single world of a WIT document with a core module in the text
+/// format, whose exports are named after the functions of the world, e.g. `add` or
+/// `test:p/math#add` for a function of an exported interface.
+pub fn component_with(wit: &str, wat: &str) -> Vec<u8> {
+    component_with_deps(&[], wit, wat)
+}
+
+/// A component as [`component_with`] builds it, whose WIT document uses the packages of the
+/// `deps` documents, given in dependency order.
+pub fn component_with_deps(deps: &[&str], wit: &str, wat: &str) -> Vec<u8> {
+    let (resolve, world) = world_with_deps(deps, wit);
+
+    encode(wat.as_bytes(), &resolve, world)
+}
+
+fn world(wit: &str) -> (Resolve, WorldId) {
+    world_with_deps(&[], wit)
+}
+
+fn world_with_deps(deps: &[&str], wit: &str) -> (Resolve, WorldId) {
+    let mut resolve = Resolve::default();
+    let mut package = None;
+
+    for wit in deps.iter().chain([&wit]) {
+        let unresolved = UnresolvedPackage::parse(Path::new("test.wit"), wit).unwrap();
+        package = Some(resolve.push(unresolved).unwrap());
+    }
+
+    let world = resolve.select_world(package.unwrap(), None).unwrap();
+
+    (resolve, world)
+}
+
+fn encode(module: &[u8], resolve: &Resolve, world: WorldId) -> Vec<u8> {
+    let mut module = wat::parse_bytes(module).unwrap().into_owned();
+    wit_component::embed_component_metadata(&mut module, resolve, world, StringEncoding::UTF8)
+        .unwrap();
+
+    ComponentEncoder::default()
+        .module(&module)
+        .unwrap()
+        .validate(true)
+        .encode()
+        .unwrap()
+}
+
+/// A pool of up to two instances of a component, for two calls to run at once.
+pub fn pool(bytes: &[u8]) -> Arc<InstancePool> {
+    let engine = Engine::new(Config::new().wasm_component_model(true)).unwrap();
+    let component = Component::from_binary(&engine, bytes).unwrap();
+    let pre = Linker::<Host>::new(&engine)
+        .instantiate_pre(&component)
+        .unwrap();
+
+    Arc::new(InstancePool::new(
+        &engine,
+        pre,
+        defaults::<WasiOptions>(),
+        2,
+        None,
+        None,
+    ))
+}
+
+/// The endpoints of the functions exported by the world of a WIT document, implemented by
+/// functions that trap.
+pub fn endpoints(wit: &str, options: ValueOptions) -> Vec<Endpoint> {
+    endpoints_of(&component(wit), wit, options)
+}
+
+/// The endpoints of the functions exported by the world of a WIT document, implemented by a
+/// component of it.
+///
+/// The functions are listed from the document rather than the component, which doesn't keep
+/// their docs.
+pub fn endpoints_of(component: &[u8], wit: &str, options: ValueOptions) -> Vec<Endpoint> {
+    let (resolve, world) = world(wit);
+    let package = resolve.worlds[world].package.unwrap();
+    let decoded = DecodedWasm::WitPackage(resolve, package);
+    let functions = list_wasm_component_functions(&decoded, None, None);
+
+    get_endpoints(
+        &Arc::new(decoded.resolve().clone()),
+        functions,
+        &pool(component),
+        None,
+        &defaults(),
+        &Arc::new(options),
+        &(Arc::new(()) as Arc<dyn SchemaMapper>),
+    )
+    .unwrap()
+}
+
+/// The runtime types of the parameters of a function exported by the world of a WIT document.
+pub fn param_types(wit: &str, function: &str) -> Vec<Type> {
+    let pool = pool(&component(wit));
+    let mut instance = pool.get().unwrap();
+    let func = instance.func(None, function).unwrap();
+
+    func.params(&*instance).to_vec()
+}
+
+/// A file of the temporary directory, removed once dropped.
+pub struct TempFile(PathBuf);
+
+impl TempFile {
+    pub fn new(extension: &str, contents: impl AsRef<[u8]>) -> Self {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+        let name = format!(
+            "wasm2openapi-{}-{}.{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed),
+            extension
+        );
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, contents).unwrap();
+
+        Self(path)
+    }
+
+    pub fn path(&self) -> &str {
+        self.0.to_str().unwrap()
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// A server started on a port picked by the system, stopped once dropped.
+pub struct TestServer {
+    handle: ServerHandle,
+    /// The URL of the server, e.g. `http://127.0.0.1:34567`.
+    pub url: String,
+    _file: Option<TempFile>,
+}
+
+impl TestServer {
+    /// Serve a component with the options of the command line preceding the `serve` command,
+    /// then the ones following it.
+    pub async fn start(component: &[u8], options: &[&str], serve: &[&str]) -> Self {
+        let file = TempFile::new("wasm", component);
+        let mut server = Self::start_file(file.path(), options, serve).await;
+        server._file = Some(file);
+
+        server
+    }
+
+    /// Serve the component of a path or URL with the options of the command line preceding the
+    /// `serve` command, then the ones following it.
+    pub async fn start_file(file: &str, options: &[&str], serve: &[&str]) -> Self {
+        let args = ["wasm2openapi", "--file", file, "--no-cache"]
+            .into_iter()
+            .chain(options.iter().copied())
+            .chain(["serve", "--port", "0"])
+            .chain(serve.iter().copied());
+        let cli = Cli::try_parse_from(args).unwrap();
+        let (server, address) = start(cli, &mut vec![], &mut vec![]).await.unwrap().unwrap();
+        let handle = server.handle();
+        actix_web::rt::spawn(server);
+
+        Self {
+            handle,
+            url: format!("http://{}", address),
+            _file: None,
+        }
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        drop(self.handle.stop(false));
+    }
+}
@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+use actix_web::http::StatusCode;
+use wasmtime::Engine;
+
+use crate::error::ApiError;
+
+/// The period the epoch of the engine is incremented at, which is the precision of the timeouts.
+const TICK: Duration = Duration::from_millis(10);
+
+/// Increment the epoch of the engine in the background, for the deadlines of the stores to expire.
+pub fn start_ticker(engine: &Engine) {
+    let engine = engine.clone();
+
+    std::thread::spawn(move || loop {
+        std::thread::sleep(TICK);
+        engine.increment_epoch();
+    });
+}
+
+/// The number of epochs before the deadline of a call, rounded up so that it never expires early.
+pub fn deadline(timeout: Duration) -> u64 {
+    (timeout.as_nanos().div_ceil(TICK.as_nanos()) + 1) as u64
+}
+
+pub fn timed_out() -> ApiError {
+    ApiError::new(StatusCode::GATEWAY_TIMEOUT, "the function call timed out")
+}
@@ -0,0 +1,63 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use clap::Args;
+use rustls::{Certificate, PrivateKey, ServerConfig};
+use rustls_pemfile::Item;
+
+/// Options of the HTTPS serving of the API, which is served over plain HTTP by default.
+#[derive(Args, Debug, Clone)]
+pub struct TlsOptions {
+    /// PEM file of the certificate chain to serve the API over HTTPS with
+    #[clap(long, requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+
+    /// PEM file of the private key of the --tls-cert certificate
+    #[clap(long, requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+}
+
+impl TlsOptions {
+    /// The TLS configuration of the server, if the API is served over HTTPS.
+    pub fn config(&self) -> anyhow::Result<Option<ServerConfig>> {
+        let (Some(cert), Some(key)) = (&self.tls_cert, &self.tls_key) else {
+            return Ok(None);
+        };
+
+        let certs = read_pem(cert)?
+            .into_iter()
+            .filter_map(|item| match item {
+                Item::X509Certificate(cert) => Some(Certificate(cert)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if certs.is_empty() {
+            bail!("{} contains no certificate", cert.display());
+        }
+
+        let key = read_pem(key)?
+            .into_iter()
+            .find_map(|item| match item {
+                Item::PKCS8Key(key) | Item::RSAKey(key) | Item::ECKey(key) => Some(PrivateKey(key)),
+                _ => None,
+            })
+            .with_context(|| format!("{} contains no private key", key.display()))?;
+
+        let config = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .context("Invalid TLS certificate or private key")?;
+
+        Ok(Some(config))
+    }
+}
+
+fn read_pem(path: &Path) -> anyhow::Result<Vec<Item>> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+
+    rustls_pemfile::read_all(&mut BufReader::new(file))
+        .with_context(|| format!("Failed to parse {} as PEM", path.display()))
+}
@@ -0,0 +1,176 @@
+use anyhow::anyhow;
+use jsonschema::{Draft, JSONSchema};
+use serde_json::{json, Value};
+
+/// Compile a schema of the OpenAPI document into a JSON Schema validator, resolving its
+/// references against the components of the document.
+pub fn validator(schema: &Value, spec: &Value) -> anyhow::Result<JSONSchema> {
+    let mut root = json!({ "allOf": [schema] });
+
+    if let Some(components) = spec.get("components") {
+        root["components"] = components.clone();
+    }
+    translate_nullable(&mut root);
+
+    JSONSchema::options()
+        .with_draft(Draft::Draft4)
+        .compile(&root)
+        .map_err(|e| anyhow!("Invalid schema: {}", e))
+}
+
+/// The schema of the requests of the operation of a path item: the schema of its JSON request
+/// body, or the object of its query parameters when it has none (e.g. a GET operation).
+pub fn request_schema(item: &Value) -> Option<Value> {
+    let operation = ["post", "put", "patch", "delete", "get"]
+        .iter()
+        .find_map(|method| item.get(method))?;
+
+    if let Some(body) = operation.get("requestBody") {
+        return body.pointer("/content/application~1json/schema").cloned();
+    }
+
+    let mut properties = serde_json::Map::new();
+    let mut required = vec![];
+
+    for parameter in operation
+        .get("parameters")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|parameter| parameter.get("in") == Some(&json!("query")))
+    {
+        let name = parameter.get("name")?.as_str()?;
+
+        properties.insert(
+            name.to_string(),
+            parameter.get("schema").cloned().unwrap_or(json!({})),
+        );
+        if parameter.get("required") == Some(&Value::Bool(true)) {
+            required.push(json!(name));
+        }
+    }
+
+    let mut schema = json!({ "type": "object", "properties": properties });
+    if !required.is_empty() {
+        schema["required"] = Value::Array(required);
+    }
+
+    Some(schema)
+}
+
+/// The validation errors of a value, prefixed with the JSON pointer of the invalid value.
+pub fn errors(validator: &JSONSchema, value: &Value) -> Vec<String> {
+    match validator.validate(value) {
+        Ok(()) => vec![],
+        Err(errors) => errors
+            .map(|e| match e.instance_path.to_string() {
+                path if path.is_empty() => e.to_string(),
+                path => format!("`{}`: {}", path, e),
+            })
+            .collect(),
+    }
+}
+
+/// Reject the properties of the object schemas that they don't declare, unless they say otherwise.
+pub fn close_objects(schema: &mut Value) {
+    match schema {
+        Value::Object(object) => {
+            if object.contains_key("properties") && !object.contains_key("additionalProperties") {
+                object.insert(String::from("additionalProperties"), Value::Bool(false));
+            }
+
+            for (keyword, value) in object.iter_mut() {
+                match keyword.as_str() {
+                    // The values of these keywords are maps of schemas, or not schemas at all
+                    "properties" => value
+                        .as_object_mut()
+                        .into_iter()
+                        .flat_map(|properties| properties.values_mut())
+                        .for_each(close_objects),
+                    "enum" | "example" | "default" => {}
+                    _ => close_objects(value),
+                }
+            }
+        }
+        Value::Array(schemas) => schemas.iter_mut().for_each(close_objects),
+        _ => {}
+    }
+}
+
+/// Replace the OpenAPI `nullable` keyword, which JSON Schema doesn't have, with a `null` case.
+pub fn translate_nullable(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            for value in object.values_mut() {
+                translate_nullable(value);
+            }
+
+            if object.remove("nullable") == Some(Value::Bool(true)) {
+                let schema = value.take();
+
+                *value = json!({ "anyOf": [{ "type": "null" }, schema] });
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(translate_nullable),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_request_schemas() {
+        let schema = json!({ "type": "object", "properties": { "a": { "type": "integer" } } });
+        let post = json!({
+            "post": { "requestBody": { "content": { "application/json": { "schema": schema } } } }
+        });
+        assert_eq!(request_schema(&post), Some(schema));
+
+        let get = json!({
+            "get": {
+                "parameters": [
+                    { "name": "a", "in": "query", "required": true, "schema": { "type": "integer" } },
+                    { "name": "b", "in": "query", "schema": { "$ref": "#/components/schemas/b" } },
+                    { "name": "c", "in": "header", "schema": { "type": "string" } },
+                ]
+            }
+        });
+        assert_eq!(
+            request_schema(&get),
+            Some(json!({
+                "type": "object",
+                "properties": {
+                    "a": { "type": "integer" },
+                    "b": { "$ref": "#/components/schemas/b" },
+                },
+                "required": ["a"],
+            }))
+        );
+
+        assert_eq!(
+            request_schema(&json!({ "get": {} })),
+            Some(json!({ "type": "object", "properties": {} }))
+        );
+        assert_eq!(request_schema(&json!({ "summary": "none" })), None);
+    }
+
+    #[test]
+    fn validates_against_the_components() {
+        let spec = json!({
+            "components": { "schemas": { "b": { "type": "string", "nullable": true } } }
+        });
+        let schema = json!({
+            "type": "object",
+            "properties": { "b": { "$ref": "#/components/schemas/b" } },
+            "required": ["b"],
+        });
+        let validator = validator(&schema, &spec).unwrap();
+
+        assert!(errors(&validator, &json!({ "b": "x" })).is_empty());
+        assert!(errors(&validator, &json!({ "b": null })).is_empty());
+        assert_eq!(errors(&validator, &json!({ "b": 1 })).len(), 1);
+        assert_eq!(errors(&validator, &json!({})).len(), 1);
+    }
+}
@@ -0,0 +1,318 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::web::Bytes;
+use anyhow::{anyhow, Context};
+use clap::Args;
+use wasmtime::component::Linker;
+use wasmtime_wasi::preview2::{
+    command, DirPerms, FilePerms, HostOutputStream, StdoutStream, StreamError, Subscribe, Table,
+    WasiCtx, WasiCtxBuilder, WasiView,
+};
+use wasmtime_wasi::{ambient_authority, Dir};
+
+/// Options of the WASI host functions the components may import, which aren't linked by default.
+#[derive(Args, Debug, Clone)]
+pub struct WasiOptions {
+    /// Link the WASI host functions, for the components importing `wasi:cli`, `wasi:io`, etc.
+    #[clap(long)]
+    wasi: bool,
+
+    /// Give the components access to a host directory, as `<host>` or `<host>::<guest>` to
+    /// mount it at another path
+    #[clap(long, requires = "wasi")]
+    wasi_dir: Vec<PreopenedDir>,
+
+    /// Set an environment variable of the components, as `<name>=<value>`
+    #[clap(long, requires = "wasi", value_parser = parse_env)]
+    wasi_env: Vec<(String, String)>,
+
+    /// Debug the components by returning what they write to the standard error during a call in
+    /// the `x-stderr` header of its response, instead of the standard error of the server
+    #[clap(long, requires = "wasi")]
+    echo_stderr: bool,
+}
+
+/// The header echoing the standard error of the call, with `--echo-stderr`.
+pub const X_STDERR: &str = "x-stderr";
+
+/// The bytes of the standard error kept for a call, the rest being dropped.
+const MAX_STDERR: usize = 4096;
+
+#[derive(Debug, Clone)]
+pub struct PreopenedDir {
+    host: PathBuf,
+    guest: String,
+}
+
+impl FromStr for PreopenedDir {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (host, guest) = s.split_once("::").unwrap_or((s, s));
+
+        if host.is_empty() || guest.is_empty() {
+            return Err(String::from("expected `<host>` or `<host>::<guest>`"));
+        }
+
+        Ok(Self {
+            host: PathBuf::from(host),
+            guest: guest.to_string(),
+        })
+    }
+}
+
+fn parse_env(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.to_string(), value.to_string())),
+        _ => Err(String::from("expected `<name>=<value>`")),
+    }
+}
+
+/// The data of the stores, holding the WASI context of the instance.
+pub struct Host {
+    table: Table,
+    wasi: WasiCtx,
+    /// The standard error of the instance, captured with `--echo-stderr`.
+    stderr: Option<CapturedStderr>,
+}
+
+impl Host {
+    /// Take what the instance wrote to its standard error since the last time, when captured.
+    pub fn take_stderr(&mut self) -> Option<Vec<u8>> {
+        self.stderr.as_ref().map(CapturedStderr::take)
+    }
+}
+
+/// The standard error of an instance, written to memory up to [`MAX_STDERR`] bytes.
+#[derive(Clone, Default)]
+struct CapturedStderr(Arc<Mutex<Vec<u8>>>);
+
+impl CapturedStderr {
+    fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+impl StdoutStream for CapturedStderr {
+    fn stream(&self) -> Box<dyn HostOutputStream> {
+        Box::new(self.clone())
+    }
+
+    fn isatty(&self) -> bool {
+        false
+    }
+}
+
+impl HostOutputStream for CapturedStderr {
+    fn write(&mut self, bytes: Bytes) -> Result<(), StreamError> {
+        let mut buffer = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let len = bytes.len().min(MAX_STDERR - buffer.len().min(MAX_STDERR));
+
+        buffer.extend_from_slice(&bytes[..len]);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        Ok(())
+    }
+
+    fn check_write(&mut self) -> Result<usize, StreamError> {
+        // The bytes past the maximum are dropped rather than failing the writes
+        Ok(MAX_STDERR)
+    }
+}
+
+#[async_trait::async_trait]
+impl Subscribe for CapturedStderr {
+    async fn ready(&mut self) {}
+}
+
+/// The `x-stderr` header, with the bytes which can't be sent escaped.
+pub fn stderr_header(stderr: &[u8]) -> (HeaderName, HeaderValue) {
+    let escaped = String::from_utf8_lossy(stderr).escape_default().to_string();
+    let value = HeaderValue::from_str(&escaped).expect("the escaped characters are visible ASCII");
+
+    (HeaderName::from_static(X_STDERR), value)
+}
+
+impl WasiView for Host {
+    fn table(&self) -> &Table {
+        &self.table
+    }
+
+    fn table_mut(&mut self) -> &mut Table {
+        &mut self.table
+    }
+
+    fn ctx(&self) -> &WasiCtx {
+        &self.wasi
+    }
+
+    fn ctx_mut(&mut self) -> &mut WasiCtx {
+        &mut self.wasi
+    }
+}
+
+impl WasiOptions {
+    /// The WASI host functions, without access to any directory nor environment variable.
+    pub fn sandboxed() -> Self {
+        Self {
+            wasi: true,
+            wasi_dir: vec![],
+            wasi_env: vec![],
+            echo_stderr: false,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.wasi
+    }
+
+    /// Define the WASI host functions, overriding the stubbed imports if any.
+    pub fn add_to_linker(&self, linker: &mut Linker<Host>) -> anyhow::Result<()> {
+        if !self.wasi {
+            return Ok(());
+        }
+
+        linker.allow_shadowing(true);
+        command::sync::add_to_linker(linker).context("Failed to link the WASI host functions")
+    }
+
+    /// The data of a new store, with nothing but the standard error, the directories and the
+    /// environment variables given on the command line.
+    pub fn host(&self) -> anyhow::Result<Host> {
+        let mut builder = WasiCtxBuilder::new();
+        let stderr = (self.wasi && self.echo_stderr).then(CapturedStderr::default);
+
+        if self.wasi {
+            match &stderr {
+                Some(stderr) => builder.stderr(stderr.clone()),
+                None => builder.inherit_stderr(),
+            }
+            .envs(&self.wasi_env);
+
+            for dir in &self.wasi_dir {
+                let host = Dir::open_ambient_dir(&dir.host, ambient_authority())
+                    .map_err(|e| anyhow!("Failed to open {}: {}", dir.host.display(), e))?;
+
+                builder.preopened_dir(host, DirPerms::all(), FilePerms::all(), &dir.guest);
+            }
+        }
+
+        Ok(Host {
+            table: Table::new(),
+            wasi: builder.build(),
+            stderr,
+        })
+    }
+}
+
+/// Run `f` on a thread of its own when called from the asynchronous runtime, which the
+/// synchronous WASI host functions can't block on as they do outside of it.
+pub fn outside_runtime<R: Send>(f: impl FnOnce() -> R + Send) -> R {
+    if tokio::runtime::Handle::try_current().is_err() {
+        return f();
+    }
+
+    std::thread::scope(|scope| {
+        scope
+            .spawn(f)
+            .join()
+            .unwrap_or_else(|e| std::panic::resume_unwind(e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::{self, TestServer};
+
+    /// The parts of the WASI packages linked by the server which write to the standard error.
+    const WASI_IO: &str = r#"
+        package wasi:io@0.2.0-rc-2023-11-10;
+
+        interface error {
+            resource error;
+        }
+
+        interface streams {
+            use error.{error};
+
+            variant stream-error {
+                last-operation-failed(error),
+                closed
+            }
+
+            resource output-stream {
+                blocking-write-and-flush: func(contents: list<u8>) -> result<_, stream-error>;
+            }
+        }
+    "#;
+    const WASI_CLI: &str = r#"
+        package wasi:cli@0.2.0-rc-2023-11-10;
+
+        interface stderr {
+            use wasi:io/streams@0.2.0-rc-2023-11-10.{output-stream};
+
+            get-stderr: func() -> output-stream;
+        }
+    "#;
+
+    #[actix_web::test]
+    async fn echoes_the_standard_error_of_the_calls() {
+        let component = testing::component_with_deps(
+            &[WASI_IO, WASI_CLI],
+            r#"
+                package test:logs;
+
+                world logs {
+                    import wasi:cli/stderr@0.2.0-rc-2023-11-10;
+
+                    export double: func(x: u32) -> u32;
+                }
+            "#,
+            r#"
+                (module
+                    (import "wasi:cli/stderr@0.2.0-rc-2023-11-10" "get-stderr"
+                        (func $get-stderr (result i32)))
+                    (import "wasi:io/streams@0.2.0-rc-2023-11-10"
+                        "[method]output-stream.blocking-write-and-flush"
+                        (func $write (param i32 i32 i32 i32)))
+                    (import "wasi:io/streams@0.2.0-rc-2023-11-10" "[resource-drop]output-stream"
+                        (func $drop (param i32)))
+                    (memory (export "memory") 1)
+                    (data (i32.const 0) "doubling\n")
+                    (func (export "double") (param i32) (result i32)
+                        (local i32)
+                        (local.set 1 (call $get-stderr))
+                        (call $write (local.get 1) (i32.const 0) (i32.const 9) (i32.const 16))
+                        (call $drop (local.get 1))
+                        (i32.mul (local.get 0) (i32.const 2))))
+            "#,
+        );
+        let double = |server: &TestServer| {
+            reqwest::Client::new()
+                .post(format!("{}/double", server.url))
+                .json(&json!({ "x": 21 }))
+                .send()
+        };
+
+        // Twice, to check that each response only echoes its own call
+        let server = TestServer::start(&component, &["--wasi", "--echo-stderr"], &[]).await;
+        for _ in 0..2 {
+            let response = double(&server).await.unwrap();
+
+            assert_eq!(response.headers()[X_STDERR], "doubling\\n");
+            assert_eq!(response.text().await.unwrap(), "42");
+        }
+
+        let server = TestServer::start(&component, &["--wasi"], &[]).await;
+        let response = double(&server).await.unwrap();
+        assert_eq!(response.headers().get(X_STDERR), None);
+    }
+}
@@ -0,0 +1,164 @@
+use anyhow::{anyhow, bail};
+use serde_json::{json, Map, Value};
+
+const METHODS: &[&str] = &[
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// An outbound call of the component, documented with the
+/// `@webhook <name> <method> <path> [description]` doc annotation.
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    pub name: String,
+    pub method: String,
+    pub path: String,
+    pub description: Option<String>,
+}
+
+impl Webhook {
+    pub fn from_annotations<'a>(
+        annotations: impl Iterator<Item = (&'a str, &'a str)>,
+    ) -> anyhow::Result<Vec<Self>> {
+        annotations
+            .filter(|(tag, _)| *tag == "webhook")
+            .map(|(_, value)| {
+                Self::parse(value).map_err(|e| anyhow!("invalid @webhook `{}`: {}", value, e))
+            })
+            .collect()
+    }
+
+    fn parse(value: &str) -> anyhow::Result<Self> {
+        let mut words = value.splitn(4, char::is_whitespace);
+        let (Some(name), Some(method), Some(path)) = (words.next(), words.next(), words.next())
+        else {
+            bail!("expected `<name> <method> <path> [description]`");
+        };
+        let method = method.to_lowercase();
+
+        if !METHODS.contains(&method.as_str()) {
+            bail!("unknown method `{}`", method);
+        }
+        if !path.starts_with('/') {
+            bail!("the path must start with `/`");
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            method,
+            path: path.to_string(),
+            description: words.next().map(|s| s.trim().to_string()),
+        })
+    }
+}
+
+/// Document the webhooks, along with the schema of the payload they're sent with, in the
+/// `x-webhooks` extension since OpenAPI 3.0 has no webhooks section.
+///
+/// A webhook may be sent with several methods, so its operations are identified as
+/// `<name>-<method>` (e.g. `user-created-post`).
+pub fn add_webhooks<'a>(spec: &mut Value, webhooks: impl Iterator<Item = (&'a Webhook, Value)>) {
+    let mut items = Map::new();
+
+    for (webhook, payload) in webhooks {
+        let operation = json!({
+            "operationId": format!("{}-{}", webhook.name, webhook.method),
+            "summary": webhook.description.clone().unwrap_or_default(),
+            "description": format!("Sent to the `{}` path of the subscribers.", webhook.path),
+            "requestBody": {
+                "content": {
+                    "application/json": { "schema": payload },
+                },
+            },
+            "responses": {
+                "200": { "description": "The webhook was received" },
+            },
+        });
+        let item = items
+            .entry(webhook.name.clone())
+            .or_insert_with(|| Value::Object(Map::new()));
+
+        if let Some(item) = item.as_object_mut() {
+            item.insert(webhook.method.clone(), operation);
+        }
+    }
+
+    if !items.is_empty() {
+        if let Some(spec) = spec.as_object_mut() {
+            spec.insert(String::from("x-webhooks"), Value::Object(items));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_annotations() {
+        let webhooks = Webhook::from_annotations(
+            [
+                ("webhook", "created POST /users A user was created"),
+                ("param", "name The name"),
+                ("webhook", "deleted delete /users"),
+            ]
+            .into_iter(),
+        )
+        .unwrap();
+
+        assert_eq!(webhooks.len(), 2);
+        assert_eq!(webhooks[0].name, "created");
+        assert_eq!(webhooks[0].method, "post");
+        assert_eq!(webhooks[0].path, "/users");
+        assert_eq!(
+            webhooks[0].description.as_deref(),
+            Some("A user was created")
+        );
+        assert_eq!(webhooks[1].description, None);
+    }
+
+    #[test]
+    fn rejects_the_invalid_annotations() {
+        for (value, error) in [
+            (
+                "created post",
+                "expected `<name> <method> <path> [description]`",
+            ),
+            ("created send /users", "unknown method `send`"),
+            ("created post users", "the path must start with `/`"),
+        ] {
+            let e = Webhook::from_annotations([("webhook", value)].into_iter()).unwrap_err();
+
+            assert_eq!(
+                e.to_string(),
+                format!("invalid @webhook `{}`: {}", value, error)
+            );
+        }
+    }
+
+    #[test]
+    fn identifies_the_operations_by_name_and_method() {
+        let webhooks = Webhook::from_annotations(
+            [
+                ("webhook", "changed post /users"),
+                ("webhook", "changed put /users"),
+            ]
+            .into_iter(),
+        )
+        .unwrap();
+        let mut spec = json!({});
+
+        add_webhooks(
+            &mut spec,
+            webhooks.iter().map(|webhook| (webhook, json!({}))),
+        );
+
+        assert_eq!(
+            spec.pointer("/x-webhooks/changed/post/operationId"),
+            Some(&json!("changed-post"))
+        );
+        assert_eq!(
+            spec.pointer("/x-webhooks/changed/put/operationId"),
+            Some(&json!("changed-put"))
+        );
+    }
+}